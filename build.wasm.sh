@@ -3,7 +3,7 @@ set -e
 
 # Build the Rust project to WebAssembly using wasm-pack
 echo "Building Rust project to WebAssembly..."
-wasm-pack build --target web --release
+wasm-pack build --target web --release -- --no-default-features --features wasm
 
 # Create webui/pkg if it doesn't exist
 mkdir -p webui/pkg
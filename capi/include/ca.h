@@ -0,0 +1,144 @@
+#ifndef CA_H
+#define CA_H
+
+/* Generated by cbindgen from src/lib.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// How cells beyond the edges of a row are treated.
+typedef enum CaBoundary {
+  // Cells beyond the edges are always dead.
+  CA_BOUNDARY_DEAD,
+  // The row wraps around into a ring.
+  CA_BOUNDARY_PERIODIC,
+} CaBoundary;
+
+// Which family of update rules the automaton uses.
+typedef enum CaRuleFamily {
+  // The next state depends only on the neighbourhood.
+  CA_RULE_FAMILY_ELEMENTARY,
+  // `rule(neighbourhood) XOR previous state`, exactly reversible.
+  CA_RULE_FAMILY_SECOND_ORDER,
+} CaRuleFamily;
+
+// Shape drawn for a cell, as the CLI's `--alive-shape` and `--dead-shape`.
+typedef enum CaCellShape {
+  CA_CELL_SHAPE_SQUARE,
+  CA_CELL_SHAPE_CIRCLE,
+  CA_CELL_SHAPE_CIRCLE_SMALL,
+  CA_CELL_SHAPE_TRIANGLE_UP,
+  CA_CELL_SHAPE_TRIANGLE_DOWN,
+  CA_CELL_SHAPE_TRIANGLE_LEFT,
+  CA_CELL_SHAPE_TRIANGLE_RIGHT,
+  // Right-angled triangle filling the bottom-left half
+  CA_CELL_SHAPE_TRIANGLE_RA,
+  // Right-angled triangle filling the bottom-right half
+  CA_CELL_SHAPE_TRIANGLE_RB,
+  // Right-angled triangle filling the top-right half
+  CA_CELL_SHAPE_TRIANGLE_RC,
+  // Right-angled triangle filling the top-left half
+  CA_CELL_SHAPE_TRIANGLE_RD,
+} CaCellShape;
+
+// An automaton created by `ca_automaton_new`.
+typedef struct CaAutomaton CaAutomaton;
+
+// An RGB colour.
+typedef struct CaColor {
+  uint8_t r;
+  uint8_t g;
+  uint8_t b;
+} CaColor;
+
+// Image options, as the CLI's render options. Start from `ca_render_config_default`.
+typedef struct CaRenderConfig {
+  // Each cell becomes `scale` x `scale` pixels.
+  size_t scale;
+  enum CaCellShape alive_shape;
+  enum CaCellShape dead_shape;
+  // Draw links between neighbouring alive cells.
+  bool use_links;
+  struct CaColor dead_color_from;
+  struct CaColor dead_color_to;
+  struct CaColor alive_color_from;
+  struct CaColor alive_color_to;
+} CaRenderConfig;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create an automaton with a random initial row (cells alive with probability
+// `random_distribution`), or a single centre cell when the distribution is not positive.
+//
+// `seed` points to the seed, or is null for OS randomness. `seed_scheme_version` is 1, 2
+// or 3, or 0 for the latest. Returns null if `width` is 0, the distribution is NaN or
+// above 1, or the seed scheme is unknown. Free the automaton with `ca_automaton_free`.
+struct CaAutomaton *ca_automaton_new(uint8_t rule,
+                                     double random_distribution,
+                                     size_t width,
+                                     const uint64_t *seed,
+                                     uint8_t seed_scheme_version,
+                                     enum CaBoundary boundary,
+                                     enum CaRuleFamily family);
+
+// Free an automaton created by `ca_automaton_new`. Null is ignored.
+void ca_automaton_free(struct CaAutomaton *automaton);
+
+// Number of cells in a row, or 0 for null.
+size_t ca_automaton_width(const struct CaAutomaton *automaton);
+
+// Index of the current generation (0 for the initial row), or 0 for null.
+size_t ca_automaton_generation(const struct CaAutomaton *automaton);
+
+// Advance the automaton by one generation. Null is ignored.
+void ca_automaton_step(struct CaAutomaton *automaton);
+
+// Copy the current row (one byte per cell, 0 or 1) into `out`, which must hold exactly
+// `ca_automaton_width` bytes.
+bool ca_automaton_copy_row(const struct CaAutomaton *automaton, uint8_t *out, size_t out_len);
+
+// Copy `generations` rows, starting with the current one, into `out` (width x
+// generations bytes), leaving the automaton on the last of them.
+bool ca_automaton_run(struct CaAutomaton *automaton,
+                      size_t generations,
+                      uint8_t *out,
+                      size_t out_len);
+
+// Same as the web UI's `run_automaton`: `generations` rows of a new automaton, written
+// into `out` (width x generations bytes). It always uses dead boundaries and the
+// elementary family; use `ca_automaton_new` for the others.
+bool ca_run_automaton(uint8_t rule,
+                      double random_distribution,
+                      size_t width,
+                      size_t generations,
+                      const uint64_t *seed,
+                      uint8_t seed_scheme_version,
+                      uint8_t *out,
+                      size_t out_len);
+
+// The CLI's default render options: scale 1, square cells, no links and its colours.
+struct CaRenderConfig ca_render_config_default(void);
+
+// Bytes of RGBA pixels in an image of `generations` rows of `width` cells, or 0 if it
+// is larger than any buffer can be.
+size_t ca_render_size(const struct CaRenderConfig *config, size_t width, size_t generations);
+
+// Draw `generations` rows, starting with the current one, as RGBA pixels into `out`
+// (`ca_render_size` bytes, `width * scale` pixels per line), leaving the automaton on the
+// last of them. The image is the same as the CLI's and the web UI's.
+bool ca_automaton_render(struct CaAutomaton *automaton,
+                         size_t generations,
+                         const struct CaRenderConfig *config,
+                         uint8_t *out,
+                         size_t out_len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CA_H */
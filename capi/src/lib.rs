@@ -0,0 +1,360 @@
+//! C ABI for embedding the engine in other languages. `include/ca.h` is generated from
+//! this file with cbindgen (see `cbindgen.toml`).
+//!
+//! Invalid arguments, such as null pointers or buffers of the wrong length, return null
+//! or `false`. Any other panic inside the engine is caught, printed to stderr and also
+//! reported as null or `false`, so it never unwinds into C; running out of memory still
+//! aborts. Non-null pointers must be valid for the lengths passed with them, enum values
+//! must be one of their declared constants, and automata must come from
+//! `ca_automaton_new`.
+#![allow(clippy::missing_safety_doc)]
+
+use ca::image_output::{CellShape, RgbaRenderer};
+use ca::{Automaton, Boundary, RuleFamily, SeedScheme};
+use image::Rgb;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+/// An automaton created by `ca_automaton_new`.
+pub struct CaAutomaton(Automaton);
+
+/// How cells beyond the edges of a row are treated.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub enum CaBoundary {
+    /// Cells beyond the edges are always dead.
+    Dead,
+    /// The row wraps around into a ring.
+    Periodic,
+}
+
+/// Which family of update rules the automaton uses.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub enum CaRuleFamily {
+    /// The next state depends only on the neighbourhood.
+    Elementary,
+    /// `rule(neighbourhood) XOR previous state`, exactly reversible.
+    SecondOrder,
+}
+
+/// Shape drawn for a cell, as the CLI's `--alive-shape` and `--dead-shape`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub enum CaCellShape {
+    Square,
+    Circle,
+    CircleSmall,
+    TriangleUp,
+    TriangleDown,
+    TriangleLeft,
+    TriangleRight,
+    /// Right-angled triangle filling the bottom-left half
+    TriangleRA,
+    /// Right-angled triangle filling the bottom-right half
+    TriangleRB,
+    /// Right-angled triangle filling the top-right half
+    TriangleRC,
+    /// Right-angled triangle filling the top-left half
+    TriangleRD,
+}
+
+/// An RGB colour.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CaColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// Image options, as the CLI's render options. Start from `ca_render_config_default`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CaRenderConfig {
+    /// Each cell becomes `scale` x `scale` pixels.
+    pub scale: usize,
+    pub alive_shape: CaCellShape,
+    pub dead_shape: CaCellShape,
+    /// Draw links between neighbouring alive cells.
+    pub use_links: bool,
+    pub dead_color_from: CaColor,
+    pub dead_color_to: CaColor,
+    pub alive_color_from: CaColor,
+    pub alive_color_to: CaColor,
+}
+
+impl From<CaBoundary> for Boundary {
+    fn from(boundary: CaBoundary) -> Self {
+        match boundary {
+            CaBoundary::Dead => Boundary::Dead,
+            CaBoundary::Periodic => Boundary::Periodic,
+        }
+    }
+}
+
+impl From<CaRuleFamily> for RuleFamily {
+    fn from(family: CaRuleFamily) -> Self {
+        match family {
+            CaRuleFamily::Elementary => RuleFamily::Elementary,
+            CaRuleFamily::SecondOrder => RuleFamily::SecondOrder,
+        }
+    }
+}
+
+impl From<CaCellShape> for CellShape {
+    fn from(shape: CaCellShape) -> Self {
+        match shape {
+            CaCellShape::Square => CellShape::Square,
+            CaCellShape::Circle => CellShape::Circle,
+            CaCellShape::CircleSmall => CellShape::CircleSmall,
+            CaCellShape::TriangleUp => CellShape::TriangleUp,
+            CaCellShape::TriangleDown => CellShape::TriangleDown,
+            CaCellShape::TriangleLeft => CellShape::TriangleLeft,
+            CaCellShape::TriangleRight => CellShape::TriangleRight,
+            CaCellShape::TriangleRA => CellShape::TriangleRA,
+            CaCellShape::TriangleRB => CellShape::TriangleRB,
+            CaCellShape::TriangleRC => CellShape::TriangleRC,
+            CaCellShape::TriangleRD => CellShape::TriangleRD,
+        }
+    }
+}
+
+impl From<CaColor> for Rgb<u8> {
+    fn from(color: CaColor) -> Self {
+        Rgb([color.r, color.g, color.b])
+    }
+}
+
+/// Run `f`, returning `fallback` instead if it panics.
+fn catch_panic<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// Seed scheme version from C, where 0 selects the latest.
+fn seed_scheme(version: u8) -> Option<SeedScheme> {
+    match version {
+        0 => Some(SeedScheme::LATEST),
+        v => SeedScheme::from_version(v),
+    }
+}
+
+/// Create an automaton with a random initial row (cells alive with probability
+/// `random_distribution`), or a single centre cell when the distribution is not positive.
+///
+/// `seed` points to the seed, or is null for OS randomness. `seed_scheme_version` is 1, 2
+/// or 3, or 0 for the latest. Returns null if `width` is 0, the distribution is NaN or
+/// above 1, or the seed scheme is unknown. Free the automaton with `ca_automaton_free`.
+#[no_mangle]
+pub unsafe extern "C" fn ca_automaton_new(
+    rule: u8,
+    random_distribution: f64,
+    width: usize,
+    seed: *const u64,
+    seed_scheme_version: u8,
+    boundary: CaBoundary,
+    family: CaRuleFamily,
+) -> *mut CaAutomaton {
+    let Some(scheme) = seed_scheme(seed_scheme_version) else {
+        return std::ptr::null_mut();
+    };
+    if width == 0 || random_distribution.is_nan() || random_distribution > 1.0 {
+        return std::ptr::null_mut();
+    }
+    let seed = seed.as_ref().copied();
+    catch_panic(std::ptr::null_mut(), || {
+        let automaton =
+            Automaton::new_with_scheme(rule, Some(random_distribution), width, seed, scheme)
+                .with_boundary(boundary.into())
+                .with_family(family.into());
+        Box::into_raw(Box::new(CaAutomaton(automaton)))
+    })
+}
+
+/// Free an automaton created by `ca_automaton_new`. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn ca_automaton_free(automaton: *mut CaAutomaton) {
+    if !automaton.is_null() {
+        drop(Box::from_raw(automaton));
+    }
+}
+
+/// Number of cells in a row, or 0 for null.
+#[no_mangle]
+pub unsafe extern "C" fn ca_automaton_width(automaton: *const CaAutomaton) -> usize {
+    automaton.as_ref().map_or(0, |a| a.0.row().len())
+}
+
+/// Index of the current generation (0 for the initial row), or 0 for null.
+#[no_mangle]
+pub unsafe extern "C" fn ca_automaton_generation(automaton: *const CaAutomaton) -> usize {
+    automaton.as_ref().map_or(0, |a| a.0.generation())
+}
+
+/// Advance the automaton by one generation. Null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn ca_automaton_step(automaton: *mut CaAutomaton) {
+    if let Some(automaton) = automaton.as_mut() {
+        catch_panic((), || automaton.0.step());
+    }
+}
+
+/// Copy the current row (one byte per cell, 0 or 1) into `out`, which must hold exactly
+/// `ca_automaton_width` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ca_automaton_copy_row(
+    automaton: *const CaAutomaton,
+    out: *mut u8,
+    out_len: usize,
+) -> bool {
+    let Some(automaton) = automaton.as_ref() else {
+        return false;
+    };
+    let row = automaton.0.row();
+    if out.is_null() || out_len != row.len() {
+        return false;
+    }
+    slice::from_raw_parts_mut(out, out_len).copy_from_slice(row);
+    true
+}
+
+/// Copy `generations` rows, starting with the current one, into `out` (width x
+/// generations bytes), leaving the automaton on the last of them.
+#[no_mangle]
+pub unsafe extern "C" fn ca_automaton_run(
+    automaton: *mut CaAutomaton,
+    generations: usize,
+    out: *mut u8,
+    out_len: usize,
+) -> bool {
+    let Some(automaton) = automaton.as_mut() else {
+        return false;
+    };
+    if generations == 0
+        || out.is_null()
+        || Some(out_len) != automaton.0.row().len().checked_mul(generations)
+    {
+        return false;
+    }
+    let out = slice::from_raw_parts_mut(out, out_len);
+    catch_panic(false, || {
+        out.copy_from_slice(&automaton.0.run(generations));
+        true
+    })
+}
+
+/// Same as the web UI's `run_automaton`: `generations` rows of a new automaton, written
+/// into `out` (width x generations bytes). It always uses dead boundaries and the
+/// elementary family; use `ca_automaton_new` for the others.
+#[no_mangle]
+pub unsafe extern "C" fn ca_run_automaton(
+    rule: u8,
+    random_distribution: f64,
+    width: usize,
+    generations: usize,
+    seed: *const u64,
+    seed_scheme_version: u8,
+    out: *mut u8,
+    out_len: usize,
+) -> bool {
+    let automaton = ca_automaton_new(
+        rule,
+        random_distribution,
+        width,
+        seed,
+        seed_scheme_version,
+        CaBoundary::Dead,
+        CaRuleFamily::Elementary,
+    );
+    let ok = ca_automaton_run(automaton, generations, out, out_len);
+    ca_automaton_free(automaton);
+    ok
+}
+
+/// The CLI's default render options: scale 1, square cells, no links and its colours.
+#[no_mangle]
+pub extern "C" fn ca_render_config_default() -> CaRenderConfig {
+    CaRenderConfig {
+        scale: 1,
+        alive_shape: CaCellShape::Square,
+        dead_shape: CaCellShape::Square,
+        use_links: false,
+        dead_color_from: CaColor {
+            r: 0xff,
+            g: 0xaa,
+            b: 0xff,
+        },
+        dead_color_to: CaColor { r: 0, g: 0, b: 0 },
+        alive_color_from: CaColor { r: 0, g: 0, b: 0 },
+        alive_color_to: CaColor {
+            r: 0xaa,
+            g: 0xff,
+            b: 0xff,
+        },
+    }
+}
+
+/// Bytes of RGBA pixels in an image of `generations` rows of `width` cells, or 0 if it
+/// is larger than any buffer can be.
+#[no_mangle]
+pub unsafe extern "C" fn ca_render_size(
+    config: *const CaRenderConfig,
+    width: usize,
+    generations: usize,
+) -> usize {
+    config
+        .as_ref()
+        .and_then(|config| {
+            width
+                .checked_mul(config.scale)?
+                .checked_mul(generations)?
+                .checked_mul(config.scale)?
+                .checked_mul(4)
+        })
+        .filter(|&size| size <= isize::MAX as usize)
+        .unwrap_or(0)
+}
+
+/// Draw `generations` rows, starting with the current one, as RGBA pixels into `out`
+/// (`ca_render_size` bytes, `width * scale` pixels per line), leaving the automaton on the
+/// last of them. The image is the same as the CLI's and the web UI's.
+#[no_mangle]
+pub unsafe extern "C" fn ca_automaton_render(
+    automaton: *mut CaAutomaton,
+    generations: usize,
+    config: *const CaRenderConfig,
+    out: *mut u8,
+    out_len: usize,
+) -> bool {
+    let (Some(automaton), Some(config)) = (automaton.as_mut(), config.as_ref()) else {
+        return false;
+    };
+    let width = automaton.0.row().len();
+    let size = ca_render_size(config, width, generations);
+    if generations == 0 || config.scale == 0 || size == 0 || out.is_null() || out_len != size {
+        return false;
+    }
+    let out = slice::from_raw_parts_mut(out, out_len);
+    catch_panic(false, || {
+        let mut renderer = RgbaRenderer::new(
+            Vec::new(),
+            width,
+            generations,
+            config.scale,
+            config.alive_shape.into(),
+            config.dead_shape.into(),
+            config.use_links,
+            config.dead_color_from.into(),
+            config.dead_color_to.into(),
+            config.alive_color_from.into(),
+            config.alive_color_to.into(),
+        );
+        renderer.push_row(automaton.0.row());
+        for _ in 1..generations {
+            automaton.0.step();
+            renderer.push_row(automaton.0.row());
+        }
+        out.copy_from_slice(&renderer.into_buffer());
+        true
+    })
+}
@@ -0,0 +1,110 @@
+/* Drives the C API and writes the rows and pixels it produces to stdout, for c_api.rs
+ * to compare with the Rust engine. Exits non-zero if the API disagrees with itself. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "ca.h"
+
+#define WIDTH 48
+#define GENERATIONS 32
+
+static int failures = 0;
+
+static void check(bool ok, const char *what) {
+    if (!ok) {
+        fprintf(stderr, "FAILED: %s\n", what);
+        failures++;
+    }
+}
+
+static void write_bytes(const uint8_t *bytes, size_t len) {
+    fwrite(bytes, 1, len, stdout);
+}
+
+int main(void) {
+    static uint8_t rows[WIDTH * GENERATIONS];
+    static uint8_t stepped[WIDTH * GENERATIONS];
+    uint64_t seed = 42;
+
+    /* run_automaton with a random row, a single centre cell and seed scheme 1 */
+    check(ca_run_automaton(30, 0.5, WIDTH, GENERATIONS, &seed, 0, rows, sizeof rows),
+          "run rule 30");
+    write_bytes(rows, sizeof rows);
+
+    /* Stepping and copying rows one at a time gives the same rows */
+    CaAutomaton *automaton =
+        ca_automaton_new(30, 0.5, WIDTH, &seed, 0, CA_BOUNDARY_DEAD, CA_RULE_FAMILY_ELEMENTARY);
+    check(automaton != NULL, "new");
+    check(ca_automaton_width(automaton) == WIDTH, "width");
+    for (size_t g = 0; g < GENERATIONS; g++) {
+        if (g > 0) {
+            ca_automaton_step(automaton);
+        }
+        check(ca_automaton_generation(automaton) == g, "generation");
+        check(ca_automaton_copy_row(automaton, stepped + g * WIDTH, WIDTH), "copy row");
+    }
+    check(memcmp(rows, stepped, sizeof rows) == 0, "stepped rows match run");
+    check(!ca_automaton_copy_row(automaton, stepped, WIDTH - 1), "short row buffer rejected");
+    ca_automaton_free(automaton);
+
+    check(ca_run_automaton(90, 0.0, WIDTH, GENERATIONS, NULL, 0, rows, sizeof rows),
+          "run rule 90");
+    write_bytes(rows, sizeof rows);
+
+    seed = 7;
+    check(ca_run_automaton(110, 0.5, WIDTH, GENERATIONS, &seed, 1, rows, sizeof rows),
+          "run rule 110");
+    write_bytes(rows, sizeof rows);
+
+    /* Periodic second-order run */
+    seed = 5;
+    automaton = ca_automaton_new(150, 0.3, WIDTH, &seed, 0, CA_BOUNDARY_PERIODIC,
+                                 CA_RULE_FAMILY_SECOND_ORDER);
+    check(ca_automaton_run(automaton, GENERATIONS, rows, sizeof rows), "run second order");
+    check(ca_automaton_generation(automaton) == GENERATIONS - 1, "generation after run");
+    write_bytes(rows, sizeof rows);
+    ca_automaton_free(automaton);
+
+    /* Render with circles and links, scale 3 */
+    static uint8_t pixels[40 * 3 * 30 * 3 * 4];
+    CaRenderConfig config = ca_render_config_default();
+    config.scale = 3;
+    config.alive_shape = CA_CELL_SHAPE_CIRCLE;
+    config.use_links = true;
+    check(ca_render_size(&config, 40, 30) == sizeof pixels, "render size");
+    seed = 42;
+    automaton = ca_automaton_new(30, 0.5, 40, &seed, 0, CA_BOUNDARY_DEAD,
+                                 CA_RULE_FAMILY_ELEMENTARY);
+    check(!ca_automaton_render(automaton, 30, &config, pixels, sizeof pixels - 1),
+          "short pixel buffer rejected");
+    check(ca_automaton_render(automaton, 30, &config, pixels, sizeof pixels), "render");
+    write_bytes(pixels, sizeof pixels);
+    ca_automaton_free(automaton);
+
+    /* Invalid arguments */
+    check(ca_automaton_new(30, 0.5, 0, NULL, 0, CA_BOUNDARY_DEAD, CA_RULE_FAMILY_ELEMENTARY) == NULL,
+          "zero width rejected");
+    check(ca_automaton_new(30, 0.5, 8, NULL, 4, CA_BOUNDARY_DEAD, CA_RULE_FAMILY_ELEMENTARY) == NULL,
+          "unknown seed scheme rejected");
+    check(ca_automaton_new(30, 2.0, 8, NULL, 0, CA_BOUNDARY_DEAD, CA_RULE_FAMILY_ELEMENTARY) == NULL,
+          "distribution above 1 rejected");
+    check(!ca_run_automaton(30, 0.5, WIDTH, GENERATIONS, NULL, 0, NULL, sizeof rows),
+          "null output rejected");
+
+    /* Sizes no buffer can hold fail instead of overflowing inside the engine */
+    config.scale = 1;
+    check(ca_render_size(&config, 8, SIZE_MAX / 32) == 0, "oversized image has no size");
+    automaton = ca_automaton_new(30, 0.5, 8, &seed, 0, CA_BOUNDARY_DEAD,
+                                 CA_RULE_FAMILY_ELEMENTARY);
+    check(!ca_automaton_render(automaton, SIZE_MAX / 32, &config, pixels, sizeof pixels),
+          "oversized render rejected");
+    ca_automaton_free(automaton);
+    /* A width too large to allocate panics inside the engine, which returns null */
+    check(ca_automaton_new(30, 0.5, SIZE_MAX, &seed, 0, CA_BOUNDARY_DEAD,
+                           CA_RULE_FAMILY_ELEMENTARY) == NULL,
+          "oversized width rejected");
+    ca_automaton_free(NULL);
+
+    return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,87 @@
+//! Builds `c_api.c` against the static library and the generated header, and checks
+//! that the rows and pixels it writes match `run_automaton` and the web UI's images.
+#![cfg(target_os = "linux")]
+
+use ca::{generate_automaton_image, run_automaton, Automaton, Boundary, RuleFamily};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The static library, built next to the test executable in `target/<profile>/deps`.
+fn static_library() -> PathBuf {
+    let exe = env::current_exe().expect("Test executable path");
+    exe.with_file_name("libca_capi.a")
+}
+
+fn expected_output() -> Vec<u8> {
+    let mut expected = run_automaton(30, Some(0.5), 48, 32, Some(42), None);
+    expected.extend(run_automaton(90, Some(0.0), 48, 32, None, None));
+    expected.extend(run_automaton(110, Some(0.5), 48, 32, Some(7), Some(1)));
+    expected.extend(
+        Automaton::new(150, Some(0.3), 48, Some(5))
+            .with_boundary(Boundary::Periodic)
+            .with_family(RuleFamily::SecondOrder)
+            .run(32),
+    );
+    expected.extend(
+        generate_automaton_image(
+            30,
+            Some(0.5),
+            40,
+            30,
+            Some(42),
+            None,
+            "elementary",
+            3,
+            "circle",
+            "square",
+            true,
+            "#ffaaff",
+            "#000000",
+            "#000000",
+            "#aaffff",
+        )
+        .expect("The arguments are valid"),
+    );
+    expected
+}
+
+#[test]
+fn c_program_matches_rust() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let program = Path::new(env!("CARGO_TARGET_TMPDIR")).join("c_api");
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(cc)
+        .args(["-Wall", "-Wextra", "-Werror"])
+        .arg(manifest_dir.join("tests/c_api.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(static_library())
+        .args([
+            "-lgcc_s",
+            "-lutil",
+            "-lrt",
+            "-lpthread",
+            "-lm",
+            "-ldl",
+            "-lc",
+        ])
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .expect("Failed to run the C compiler");
+    assert!(status.success(), "Compiling c_api.c failed");
+
+    let output = Command::new(&program)
+        .output()
+        .expect("Failed to run c_api");
+    assert!(
+        output.status.success(),
+        "c_api failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        output.stdout == expected_output(),
+        "C output differs from Rust"
+    );
+}
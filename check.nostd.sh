@@ -0,0 +1,15 @@
+#!/usr/bin/env bash
+set -e
+
+# Build the core for a bare-metal target, which has no std at all, so a dependency or
+# import that needs std fails here even though hosted no_std builds still link it
+TARGET=thumbv7em-none-eabihf
+rustup target add "$TARGET"
+
+echo "Building the fixed-buffer API for $TARGET..."
+cargo build --lib --no-default-features --target "$TARGET"
+
+echo "Building with alloc for $TARGET..."
+cargo build --lib --no-default-features --features alloc --target "$TARGET"
+
+echo "no_std builds complete."
@@ -11,10 +11,19 @@ OUTPUT_DIR="/tmp/ecars/rand"
 # Make sure output directory exists
 mkdir -p "$OUTPUT_DIR"
 
+# Set UNIQUE=1 to only pick the canonical rule of each symmetry class
+if [ "${UNIQUE:-0}" = "1" ]; then
+    mapfile -t RULES < <(cargo run --quiet --manifest-path ~/Lab/ecars/Cargo.toml -- symmetry --canonical-only)
+fi
+
 # Loop to generate the requested number of images
 for i in $(seq 1 "$1"); do
     # Generate random parameters for each image
-    RULE=$((RANDOM % 256))
+    if [ -n "${RULES+x}" ]; then
+        RULE=${RULES[$((RANDOM % ${#RULES[@]}))]}
+    else
+        RULE=$((RANDOM % 256))
+    fi
     DISTRIBUTION=$(echo "scale=2; $RANDOM/32767" | bc)
     BG_FROM="#$(openssl rand -hex 3)"
     BG_TO="#$(openssl rand -hex 3)"
@@ -0,0 +1,180 @@
+use crate::rng_utils::SimRng;
+use crate::{Automaton, Boundary, RuleFamily, SeedScheme, UpdateMode};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::{vec, vec::Vec};
+
+// Checkpoint layout (all integers little-endian):
+//
+//   magic "CACKPT", version u8
+//   rule u8, boundary u8, family u8, update mode u8, alpha f64, noise f64
+//   probabilities flag u8, then 8 x f64 if set
+//   generation u64, width u64, seed scheme u8 (absent in version 1, which implies 1), RNG state 4 x u64
+//   current row, then previous row, each packed 8 cells per byte (first cell in the high bit)
+
+const MAGIC: &[u8; 6] = b"CACKPT";
+const VERSION: u8 = 2;
+
+fn pack_row(out: &mut Vec<u8>, row: &[u8]) {
+    let mut packed = vec![0u8; row.len().div_ceil(8)];
+    for (x, &cell) in row.iter().enumerate() {
+        packed[x / 8] |= cell << (7 - x % 8);
+    }
+    out.extend_from_slice(&packed);
+}
+
+/// Reads fields from a checkpoint in order, failing cleanly if it is truncated.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or("Checkpoint is truncated")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(
+            self.take(8)?.try_into().expect("Slice is 8 bytes"),
+        ))
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+
+    fn row(&mut self, width: usize) -> Result<Vec<u8>, String> {
+        let packed = self.take(width.div_ceil(8))?;
+        Ok((0..width)
+            .map(|x| (packed[x / 8] >> (7 - x % 8)) & 1)
+            .collect())
+    }
+}
+
+impl Automaton {
+    /// Serialise the complete engine state to a compact binary checkpoint.
+    pub fn to_checkpoint(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(96 + self.current.len() / 4);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(self.rule);
+        out.push(match self.boundary {
+            Boundary::Dead => 0,
+            Boundary::Periodic => 1,
+        });
+        out.push(match self.family {
+            RuleFamily::Elementary => 0,
+            RuleFamily::SecondOrder => 1,
+        });
+        let (mode, alpha) = match self.update_mode {
+            UpdateMode::Synchronous => (0, 1.0),
+            UpdateMode::Alpha(alpha) => (1, alpha),
+            UpdateMode::RandomSequential => (2, 1.0),
+        };
+        out.push(mode);
+        out.extend_from_slice(&alpha.to_le_bytes());
+        out.extend_from_slice(&self.noise.to_le_bytes());
+        match self.probabilities {
+            Some(probabilities) => {
+                out.push(1);
+                for p in probabilities {
+                    out.extend_from_slice(&p.to_le_bytes());
+                }
+            }
+            None => out.push(0),
+        }
+        out.extend_from_slice(&(self.generation as u64).to_le_bytes());
+        out.extend_from_slice(&(self.current.len() as u64).to_le_bytes());
+        let (scheme, state) = self.rng.state();
+        out.push(scheme.version());
+        for word in state {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+        pack_row(&mut out, &self.current);
+        pack_row(&mut out, &self.previous);
+        out
+    }
+
+    /// Restore an automaton from `to_checkpoint`, ready to continue exactly where it stopped.
+    pub fn from_checkpoint(data: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { data, pos: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err("Not a checkpoint file".to_string());
+        }
+        let version = reader.u8()?;
+        if version == 0 || version > VERSION {
+            return Err(format!("Unsupported checkpoint version {}", version));
+        }
+        let rule = reader.u8()?;
+        let boundary = match reader.u8()? {
+            0 => Boundary::Dead,
+            1 => Boundary::Periodic,
+            b => return Err(format!("Unknown boundary {}", b)),
+        };
+        let family = match reader.u8()? {
+            0 => RuleFamily::Elementary,
+            1 => RuleFamily::SecondOrder,
+            f => return Err(format!("Unknown rule family {}", f)),
+        };
+        let mode = reader.u8()?;
+        let alpha = reader.f64()?;
+        let update_mode = match mode {
+            0 => UpdateMode::Synchronous,
+            1 => UpdateMode::Alpha(alpha),
+            2 => UpdateMode::RandomSequential,
+            m => return Err(format!("Unknown update mode {}", m)),
+        };
+        let noise = reader.f64()?;
+        let probabilities = match reader.u8()? {
+            0 => None,
+            _ => {
+                let mut probabilities = [0.0; 8];
+                for p in probabilities.iter_mut() {
+                    *p = reader.f64()?;
+                }
+                Some(probabilities)
+            }
+        };
+        let generation = reader.u64()? as usize;
+        let width = reader.u64()? as usize;
+        let scheme = match version {
+            1 => SeedScheme::V1,
+            _ => {
+                let scheme = reader.u8()?;
+                SeedScheme::from_version(scheme)
+                    .ok_or_else(|| format!("Unknown seed scheme {}", scheme))?
+            }
+        };
+        let mut state = [0u64; 4];
+        for word in state.iter_mut() {
+            *word = reader.u64()?;
+        }
+        let rng = SimRng::from_state(scheme, state)
+            .map_err(|e| format!("Checkpoint has an invalid RNG state: {}", e))?;
+        let current = reader.row(width)?;
+        let previous = reader.row(width)?;
+
+        let mut automaton = Automaton::from_row(rule, current)
+            .with_previous(previous)
+            .with_boundary(boundary)
+            .with_family(family)
+            .with_noise(noise)
+            .with_update_mode(update_mode);
+        if let Some(probabilities) = probabilities {
+            automaton = automaton.with_probabilities(probabilities);
+        }
+        automaton.generation = generation;
+        automaton.rng = rng;
+        Ok(automaton)
+    }
+}
@@ -0,0 +1,174 @@
+use crate::stats::{block_entropy, density};
+use crate::{Automaton, Boundary};
+use std::collections::HashMap;
+
+/// Wolfram's qualitative classes of cellular automaton behaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WolframClass {
+    /// Evolves to a homogeneous state.
+    Uniform = 1,
+    /// Evolves to stable or periodic structures.
+    Periodic = 2,
+    /// Chaotic, aperiodic behaviour.
+    Chaotic = 3,
+    /// Complex localized structures, sometimes long-lived.
+    Complex = 4,
+}
+
+/// Measures used to estimate a rule's class, averaged over all trials.
+#[derive(Debug, Clone)]
+pub struct ClassMetrics {
+    /// LZ78 complexity of the space-time diagram after the transient, relative to
+    /// random cells of the same density (near 0 for highly compressible, near 1 for
+    /// random-looking output).
+    pub compressibility: f64,
+    /// Change in normalised 4-block entropy from the initial to the final row.
+    pub entropy_growth: f64,
+    /// Mean fraction of cells that differ after flipping a single initial cell.
+    pub damage: f64,
+    /// Longest cycle length found, if every trial became periodic.
+    pub period: Option<usize>,
+}
+
+/// Lempel-Ziv (LZ78) phrase complexity, normalised so random bits score about 1.
+fn lz_complexity(bits: &[u8]) -> f64 {
+    if bits.len() < 2 {
+        return 0.0;
+    }
+    // Binary trie of phrases seen so far: children[node] = [child for 0, child for 1]
+    let mut children: Vec<[usize; 2]> = vec![[0, 0]];
+    let mut node = 0;
+    let mut phrases = 0usize;
+    for &bit in bits {
+        let next = children[node][bit as usize];
+        if next == 0 {
+            children.push([0, 0]);
+            children[node][bit as usize] = children.len() - 1;
+            phrases += 1;
+            node = 0;
+        } else {
+            node = next;
+        }
+    }
+    if node != 0 {
+        phrases += 1;
+    }
+    let n = bits.len() as f64;
+    phrases as f64 * (phrases as f64).log2() / n
+}
+
+/// Shannon entropy (in bits) of a biased coin with probability `p`.
+fn binary_entropy(p: f64) -> f64 {
+    if p <= 0.0 || p >= 1.0 {
+        return 0.0;
+    }
+    -p * p.log2() - (1.0 - p) * (1.0 - p).log2()
+}
+
+/// Estimate the Wolfram class of a rule from random initial conditions.
+///
+/// Each trial evolves a random ring of `width` cells for `generations` steps
+/// alongside a copy with the centre cell flipped. Trials are seeded from `seed`
+/// so the estimate is reproducible. Fails for a zero width or no trials.
+pub fn estimate_class(
+    rule: u8,
+    width: usize,
+    generations: usize,
+    trials: usize,
+    seed: u64,
+) -> Result<(WolframClass, ClassMetrics), String> {
+    if width == 0 {
+        return Err("Width must be positive".to_string());
+    }
+    if trials == 0 {
+        return Err("Trials must be positive".to_string());
+    }
+    let transient = generations / 2;
+    let mut compressibility = 0.0;
+    let mut entropy_growth = 0.0;
+    let mut damage = 0.0;
+    let mut uniform_trials = 0;
+    let mut periodic_trials = 0;
+    let mut max_period = 0;
+
+    for trial in 0..trials {
+        let mut automaton = Automaton::new(
+            rule,
+            Some(0.5),
+            width,
+            Some(seed.wrapping_add(trial as u64)),
+        )
+        .with_boundary(Boundary::Periodic);
+        let mut flipped_row = automaton.row().to_vec();
+        flipped_row[width / 2] ^= 1;
+        let mut flipped = Automaton::from_row(rule, flipped_row).with_boundary(Boundary::Periodic);
+
+        let initial_entropy = block_entropy(automaton.row(), 4) / 4.0;
+        let mut seen: HashMap<Vec<u8>, usize> = HashMap::new();
+        let mut period = None;
+        let mut bits = Vec::with_capacity((generations - transient) * width);
+        let mut differing = 0usize;
+        let mut damage_samples = 0usize;
+
+        for gen in 1..generations {
+            automaton.step();
+            flipped.step();
+            if gen >= transient {
+                bits.extend_from_slice(automaton.row());
+            }
+            if gen >= generations - generations / 4 {
+                differing += automaton
+                    .row()
+                    .iter()
+                    .zip(flipped.row())
+                    .filter(|(a, b)| a != b)
+                    .count();
+                damage_samples += width;
+            }
+            if period.is_none() {
+                if let Some(&first) = seen.get(automaton.row()) {
+                    period = Some(gen - first);
+                } else {
+                    seen.insert(automaton.row().to_vec(), gen);
+                }
+            }
+        }
+
+        let final_row = automaton.row();
+        if period == Some(1) && final_row.iter().all(|&c| c == final_row[0]) {
+            uniform_trials += 1;
+        }
+        if let Some(p) = period {
+            periodic_trials += 1;
+            max_period = max_period.max(p);
+        }
+        // Compare against random bits of the same density so sparse chaotic rules still score high
+        let expected = binary_entropy(density(&bits));
+        if expected > 0.0 {
+            compressibility += lz_complexity(&bits) / expected;
+        }
+        entropy_growth += block_entropy(final_row, 4) / 4.0 - initial_entropy;
+        damage += differing as f64 / damage_samples.max(1) as f64;
+    }
+
+    let metrics = ClassMetrics {
+        compressibility: compressibility / trials as f64,
+        entropy_growth: entropy_growth / trials as f64,
+        damage: damage / trials as f64,
+        period: if periodic_trials == trials {
+            Some(max_period)
+        } else {
+            None
+        },
+    };
+    let class = if uniform_trials == trials {
+        WolframClass::Uniform
+    } else if periodic_trials == trials || metrics.damage < 0.05 {
+        WolframClass::Periodic
+    } else if metrics.compressibility > 0.8 && metrics.damage > 0.15 {
+        WolframClass::Chaotic
+    } else {
+        WolframClass::Complex
+    };
+    Ok((class, metrics))
+}
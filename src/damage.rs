@@ -0,0 +1,43 @@
+use crate::Automaton;
+
+/// Run `automaton` for `generations` rows alongside a copy with the current cell at
+/// `flip_index` flipped, and return the XOR of the two diagrams (flat, as `Automaton::run`).
+///
+/// Both runs are clones of `automaton`, so they share its boundary, family, stochastic
+/// options and RNG state.
+pub fn damage_spread(automaton: &Automaton, generations: usize, flip_index: usize) -> Vec<u8> {
+    let mut original = automaton.clone();
+    let mut damaged = automaton.clone();
+    damaged.flip_cell(flip_index);
+    original
+        .run(generations)
+        .iter()
+        .zip(damaged.run(generations))
+        .map(|(a, b)| a ^ b)
+        .collect()
+}
+
+/// Growth rate of the difference cone, in cells per generation.
+///
+/// Fits a least-squares line to the width of the damaged region (leftmost to
+/// rightmost differing cell) over the generations in which any damage remains.
+pub fn damage_growth_rate(diff: &[u8], width: usize) -> f64 {
+    let points: Vec<(f64, f64)> = diff
+        .chunks(width)
+        .enumerate()
+        .filter_map(|(gen, row)| {
+            let left = row.iter().position(|&c| c == 1)?;
+            let right = row.iter().rposition(|&c| c == 1)?;
+            Some((gen as f64, (right - left + 1) as f64))
+        })
+        .collect();
+    if points.len() < 2 {
+        return 0.0;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let cov: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let var: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    cov / var
+}
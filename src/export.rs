@@ -0,0 +1,482 @@
+use std::io::{self, Write};
+
+/// Data formats for writing the generations of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    /// One line of `0`/`1` digits per generation.
+    Digits,
+    /// An object with the rule, the size and a `cells` array of rows.
+    Json,
+    /// One line of comma separated cells per generation.
+    Csv,
+    /// Binary portable bitmap (P4), alive cells black.
+    Pbm,
+    /// Binary portable graymap (P5), alive cells black and dead cells white.
+    Pgm,
+    /// Golly run-length encoding, with the rule written as `W<rule>`.
+    Rle,
+    /// NumPy `.npy` `uint8` array of shape (generations, width).
+    Npy,
+}
+
+impl DataFormat {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "digits" => DataFormat::Digits,
+            "json" => DataFormat::Json,
+            "csv" => DataFormat::Csv,
+            "pbm" => DataFormat::Pbm,
+            "pgm" => DataFormat::Pgm,
+            "rle" => DataFormat::Rle,
+            "npy" => DataFormat::Npy,
+            _ => panic!("Format must be one of digits, json, csv, pbm, pgm, rle, npy"),
+        }
+    }
+}
+
+/// Longest line written in RLE output, as recommended by the format.
+const RLE_LINE_LENGTH: usize = 70;
+
+/// Streams generations to `out` one row at a time in a `DataFormat`.
+///
+/// The header is written by `new`, so the number of generations must be known up front.
+pub struct DataWriter<W: Write> {
+    out: W,
+    format: DataFormat,
+    width: usize,
+    rows: usize,
+    /// Rows ended but not yet written in RLE, so blank rows collapse into one `n$` token.
+    rle_pending_rows: usize,
+    rle_line_length: usize,
+}
+
+impl<W: Write> DataWriter<W> {
+    pub fn new(
+        mut out: W,
+        format: DataFormat,
+        rule: u8,
+        width: usize,
+        generations: usize,
+    ) -> io::Result<Self> {
+        match format {
+            DataFormat::Digits | DataFormat::Csv => {}
+            DataFormat::Json => write!(
+                out,
+                "{{\"rule\":{},\"width\":{},\"generations\":{},\"cells\":[",
+                rule, width, generations
+            )?,
+            DataFormat::Pbm => write!(out, "P4\n{} {}\n", width, generations)?,
+            DataFormat::Pgm => write!(out, "P5\n{} {}\n255\n", width, generations)?,
+            DataFormat::Rle => {
+                writeln!(out, "x = {}, y = {}, rule = W{}", width, generations, rule)?
+            }
+            DataFormat::Npy => {
+                let mut header = format!(
+                    "{{'descr': '|u1', 'fortran_order': False, 'shape': ({}, {}), }}",
+                    generations, width
+                );
+                // Magic (6) + version (2) + length (2) + header must be a multiple of 64
+                let total = 10 + header.len() + 1;
+                header.push_str(&" ".repeat(total.next_multiple_of(64) - total));
+                header.push('\n');
+                out.write_all(b"\x93NUMPY\x01\x00")?;
+                out.write_all(&(header.len() as u16).to_le_bytes())?;
+                out.write_all(header.as_bytes())?;
+            }
+        }
+        Ok(DataWriter {
+            out,
+            format,
+            width,
+            rows: 0,
+            rle_pending_rows: 0,
+            rle_line_length: 0,
+        })
+    }
+
+    /// Write the next generation.
+    pub fn write_row(&mut self, row: &[u8]) -> io::Result<()> {
+        assert_eq!(row.len(), self.width, "Row must match the width");
+        match self.format {
+            DataFormat::Digits => {
+                let line: Vec<u8> = row.iter().map(|&c| b'0' + c).collect();
+                self.out.write_all(&line)?;
+                self.out.write_all(b"\n")?;
+            }
+            DataFormat::Json => {
+                let cells: Vec<&str> = row
+                    .iter()
+                    .map(|&c| if c == 1 { "1" } else { "0" })
+                    .collect();
+                let sep = if self.rows > 0 { "," } else { "" };
+                write!(self.out, "{}\n[{}]", sep, cells.join(","))?;
+            }
+            DataFormat::Csv => {
+                let cells: Vec<&str> = row
+                    .iter()
+                    .map(|&c| if c == 1 { "1" } else { "0" })
+                    .collect();
+                writeln!(self.out, "{}", cells.join(","))?;
+            }
+            DataFormat::Pbm => {
+                let mut packed = vec![0u8; self.width.div_ceil(8)];
+                for (x, &c) in row.iter().enumerate() {
+                    packed[x / 8] |= c << (7 - x % 8);
+                }
+                self.out.write_all(&packed)?;
+            }
+            DataFormat::Pgm => {
+                let gray: Vec<u8> = row.iter().map(|&c| if c == 1 { 0 } else { 255 }).collect();
+                self.out.write_all(&gray)?;
+            }
+            DataFormat::Rle => self.write_rle_row(row)?,
+            DataFormat::Npy => self.out.write_all(row)?,
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn write_rle_token(&mut self, token: &str) -> io::Result<()> {
+        if self.rle_line_length + token.len() > RLE_LINE_LENGTH {
+            writeln!(self.out)?;
+            self.rle_line_length = 0;
+        }
+        self.out.write_all(token.as_bytes())?;
+        self.rle_line_length += token.len();
+        Ok(())
+    }
+
+    fn write_rle_row(&mut self, row: &[u8]) -> io::Result<()> {
+        if self.rows > 0 {
+            self.rle_pending_rows += 1;
+        }
+        // Trailing dead cells are implied by the end of the row
+        let Some(last) = row.iter().rposition(|&c| c == 1) else {
+            return Ok(());
+        };
+        if self.rle_pending_rows > 0 {
+            let token = run_token(self.rle_pending_rows, '$');
+            self.write_rle_token(&token)?;
+            self.rle_pending_rows = 0;
+        }
+        let mut x = 0;
+        while x <= last {
+            let cell = row[x];
+            let run = row[x..=last].iter().take_while(|&&c| c == cell).count();
+            let token = run_token(run, if cell == 1 { 'o' } else { 'b' });
+            self.write_rle_token(&token)?;
+            x += run;
+        }
+        Ok(())
+    }
+
+    /// Write the footer and flush, returning the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.format {
+            DataFormat::Json => writeln!(self.out, "]}}")?,
+            DataFormat::Rle => {
+                self.write_rle_token("!")?;
+                writeln!(self.out)?;
+            }
+            _ => {}
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// An RLE run such as `3o`, with the count omitted for single cells.
+fn run_token(run: usize, tag: char) -> String {
+    if run == 1 {
+        tag.to_string()
+    } else {
+        format!("{}{}", run, tag)
+    }
+}
+
+/// Read generations written in any `DataFormat`, detecting the format from the data.
+///
+/// Rows must all have the same width, except in RLE where short rows are padded
+/// with dead cells. Any non-zero cell is alive.
+pub fn read_generations(data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let rows = match detect_format(data) {
+        DataFormat::Npy => read_npy(data)?,
+        DataFormat::Pbm | DataFormat::Pgm => read_netpbm(data)?,
+        format => {
+            let text = std::str::from_utf8(data).map_err(|_| "Input is not valid text")?;
+            match format {
+                DataFormat::Json => read_json(text)?,
+                DataFormat::Rle => read_rle(text)?,
+                _ => read_lines(text)?,
+            }
+        }
+    };
+    let width = rows
+        .first()
+        .map(|row| row.len())
+        .ok_or("Input has no rows")?;
+    if width == 0 || rows.iter().any(|row| row.len() != width) {
+        return Err("Rows must all have the same, non-zero width".to_string());
+    }
+    Ok(rows)
+}
+
+fn detect_format(data: &[u8]) -> DataFormat {
+    let text = String::from_utf8_lossy(&data[..data.len().min(256)]);
+    let trimmed = text.trim_start();
+    if data.starts_with(b"\x93NUMPY") {
+        DataFormat::Npy
+    } else if data.starts_with(b"P4") {
+        DataFormat::Pbm
+    } else if data.starts_with(b"P5") {
+        DataFormat::Pgm
+    } else if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        DataFormat::Json
+    } else if trimmed.starts_with('#') || trimmed.starts_with('x') {
+        DataFormat::Rle
+    } else if trimmed.lines().next().is_some_and(|l| l.contains(',')) {
+        DataFormat::Csv
+    } else {
+        DataFormat::Digits
+    }
+}
+
+fn alive(value: u8) -> u8 {
+    (value != 0) as u8
+}
+
+/// Digits (`0110`) or CSV (`0,1,1,0`), one generation per line.
+fn read_lines(text: &str) -> Result<Vec<Vec<u8>>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.chars()
+                .filter(|&c| c != ',' && !c.is_whitespace())
+                .map(|c| match c {
+                    '0' => Ok(0),
+                    '1' => Ok(1),
+                    _ => Err(format!("Unexpected '{}' in row", c)),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The `cells` array of a JSON object, or a bare array of rows.
+fn read_json(text: &str) -> Result<Vec<Vec<u8>>, String> {
+    let start = match text.find("\"cells\"") {
+        Some(i) => i + text[i..].find('[').ok_or("Missing cells array")?,
+        None => text.find('[').ok_or("Missing array of rows")?,
+    };
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut depth = 0;
+    for c in text[start..].chars() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 1 {
+                    rows.push(std::mem::take(&mut row));
+                } else if depth == 0 {
+                    return Ok(rows);
+                }
+            }
+            '0' | '1' if depth == 2 => row.push(c as u8 - b'0'),
+            ',' | ' ' | '\n' | '\r' | '\t' => {}
+            _ => return Err(format!("Unexpected '{}' in cells", c)),
+        }
+    }
+    Err("Unterminated cells array".to_string())
+}
+
+/// Golly RLE; `b`/`.` are dead and any other letter alive.
+fn read_rle(text: &str) -> Result<Vec<Vec<u8>>, String> {
+    let mut width = 0;
+    let mut height = None;
+    let mut rows = vec![Vec::new()];
+    let mut run = 0usize;
+    'lines: for line in text.lines().map(str::trim) {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if line.starts_with('x') {
+            let field = |name: &str| {
+                line.split(',')
+                    .find_map(|part| part.trim().strip_prefix(name)?.trim().strip_prefix('='))
+                    .map(|value| value.trim().parse::<usize>().map_err(|e| e.to_string()))
+            };
+            width = field("x").ok_or("Malformed RLE header")??;
+            height = field("y").transpose()?;
+            continue;
+        }
+        for c in line.chars() {
+            match c {
+                '0'..='9' => run = run * 10 + (c as usize - '0' as usize),
+                '$' => {
+                    for _ in 0..run.max(1) {
+                        rows.push(Vec::new());
+                    }
+                    run = 0;
+                }
+                '!' => break 'lines,
+                c if c.is_ascii_alphabetic() || c == '.' => {
+                    let cell = if c == 'b' || c == '.' { 0 } else { 1 };
+                    let row = rows.last_mut().expect("Rows are never empty");
+                    row.extend(std::iter::repeat_n(cell, run.max(1)));
+                    run = 0;
+                }
+                c if c.is_whitespace() => {}
+                _ => return Err(format!("Unexpected '{}' in RLE", c)),
+            }
+        }
+    }
+    // Trailing dead generations are implied by the header's `y`
+    if let Some(height) = height {
+        if rows.len() > height {
+            return Err(format!("RLE has {} rows but y = {}", rows.len(), height));
+        }
+        rows.resize(height, Vec::new());
+    }
+    let width = width.max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+    for row in &mut rows {
+        row.resize(width, 0);
+    }
+    Ok(rows)
+}
+
+/// Binary PBM (P4) or PGM (P5). In PBM 1 is alive; in PGM any non-white pixel is alive.
+fn read_netpbm(data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let is_pbm = data.starts_with(b"P4");
+    let fields = if is_pbm { 3 } else { 4 };
+    // Header: magic, width, height and (for PGM) maxval, separated by whitespace and comments
+    let mut values = Vec::new();
+    let mut pos = 2;
+    while values.len() < fields - 1 {
+        match data.get(pos) {
+            Some(b'#') => {
+                while data.get(pos).is_some_and(|&b| b != b'\n') {
+                    pos += 1;
+                }
+            }
+            Some(b) if b.is_ascii_whitespace() => pos += 1,
+            Some(b) if b.is_ascii_digit() => {
+                let start = pos;
+                while data.get(pos).is_some_and(|b| b.is_ascii_digit()) {
+                    pos += 1;
+                }
+                let text = std::str::from_utf8(&data[start..pos]).expect("Digits are ASCII");
+                values.push(text.parse::<usize>().map_err(|e| e.to_string())?);
+            }
+            _ => return Err("Malformed Netpbm header".to_string()),
+        }
+    }
+    // Exactly one whitespace byte separates the header from the pixels
+    let pixels = pos
+        .checked_add(1)
+        .and_then(|start| data.get(start..))
+        .ok_or("Netpbm data is truncated")?;
+    let (width, height) = (values[0], values[1]);
+    if width == 0 {
+        return Err("Netpbm image has zero width".to_string());
+    }
+    if is_pbm {
+        let stride = width.div_ceil(8);
+        if stride
+            .checked_mul(height)
+            .is_none_or(|len| pixels.len() < len)
+        {
+            return Err("PBM data is truncated".to_string());
+        }
+        Ok(pixels
+            .chunks(stride)
+            .take(height)
+            .map(|packed| {
+                (0..width)
+                    .map(|x| (packed[x / 8] >> (7 - x % 8)) & 1)
+                    .collect()
+            })
+            .collect())
+    } else {
+        let maxval = values[2];
+        if maxval > 255 {
+            return Err("Only 8-bit PGM is supported".to_string());
+        }
+        if width
+            .checked_mul(height)
+            .is_none_or(|len| pixels.len() < len)
+        {
+            return Err("PGM data is truncated".to_string());
+        }
+        Ok(pixels
+            .chunks(width)
+            .take(height)
+            .map(|row| row.iter().map(|&v| (v as usize != maxval) as u8).collect())
+            .collect())
+    }
+}
+
+/// NumPy `.npy` 2-D array of `uint8` or `bool`, in C order.
+fn read_npy(data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let truncated = || ".npy header is truncated".to_string();
+    let (header_start, header_len): (usize, usize) = match data.get(6) {
+        Some(1) => {
+            let len = data.get(8..10).ok_or_else(truncated)?;
+            (10, u16::from_le_bytes([len[0], len[1]]) as usize)
+        }
+        Some(2) | Some(3) => {
+            let len = data.get(8..12).ok_or_else(truncated)?;
+            (
+                12,
+                u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize,
+            )
+        }
+        _ => return Err("Unsupported .npy version".to_string()),
+    };
+    let header_end = header_start
+        .checked_add(header_len)
+        .filter(|&end| end <= data.len())
+        .ok_or_else(truncated)?;
+    let header = std::str::from_utf8(&data[header_start..header_end])
+        .map_err(|_| "Malformed .npy header")?;
+    if !["'|u1'", "'<u1'", "'|b1'"]
+        .iter()
+        .any(|d| header.contains(d))
+    {
+        return Err("Array must have dtype uint8 or bool".to_string());
+    }
+    if header.contains("'fortran_order': True") {
+        return Err("Array must be in C order".to_string());
+    }
+    let shape = header
+        .split("'shape':")
+        .nth(1)
+        .and_then(|s| s.split(')').next())
+        .ok_or("Missing shape")?;
+    let dims: Vec<usize> = shape
+        .trim()
+        .trim_start_matches('(')
+        .split(',')
+        .filter(|d| !d.trim().is_empty())
+        .map(|d| d.trim().parse().map_err(|_| "Malformed shape"))
+        .collect::<Result<_, _>>()?;
+    let [height, width] = dims[..] else {
+        return Err("Array must be 2-D (generations, width)".to_string());
+    };
+    if width == 0 {
+        return Err("Array has zero width".to_string());
+    }
+    let cells = &data[header_end..];
+    if width
+        .checked_mul(height)
+        .is_none_or(|len| cells.len() < len)
+    {
+        return Err(".npy data is truncated".to_string());
+    }
+    Ok(cells
+        .chunks(width)
+        .take(height)
+        .map(|row| row.iter().map(|&v| alive(v)).collect())
+        .collect())
+}
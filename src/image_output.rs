@@ -1,15 +1,94 @@
 use image::Rgb;
 use image::{ImageBuffer, Rgba};
 
+/// Shape drawn for a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellShape {
+    Square,
+    Circle,
+    CircleSmall,
+    TriangleUp,
+    TriangleDown,
+    TriangleLeft,
+    TriangleRight,
+    /// Right-angled triangle filling the bottom-left half
+    TriangleRA,
+    /// Right-angled triangle filling the bottom-right half
+    TriangleRB,
+    /// Right-angled triangle filling the top-right half
+    TriangleRC,
+    /// Right-angled triangle filling the top-left half
+    TriangleRD,
+}
+
+impl CellShape {
+    /// Parse a shape name as used by the CLI and web UI; unknown names draw squares.
+    pub fn parse(s: &str) -> Self {
+        Self::from_name(s).unwrap_or(CellShape::Square)
+    }
+
+    /// The shape called `s`, or `None` for an unknown name.
+    pub fn from_name(s: &str) -> Option<Self> {
+        Some(match s {
+            "square" => CellShape::Square,
+            "circle" => CellShape::Circle,
+            "circle-small" => CellShape::CircleSmall,
+            "triangle-up" | "triangle-r-up" => CellShape::TriangleUp,
+            "triangle-down" | "triangle-r-down" => CellShape::TriangleDown,
+            "triangle-left" => CellShape::TriangleLeft,
+            "triangle-right" => CellShape::TriangleRight,
+            "triangle-r-a" => CellShape::TriangleRA,
+            "triangle-r-b" => CellShape::TriangleRB,
+            "triangle-r-c" => CellShape::TriangleRC,
+            "triangle-r-d" => CellShape::TriangleRD,
+            _ => return None,
+        })
+    }
+}
+
+/// Parse a `#rrggbb` color, as used by the CLI, web UI and Python bindings.
+pub fn parse_hex_color(s: &str) -> Rgb<u8> {
+    try_parse_hex_color(s).expect("Color must be in format #RRGGBB")
+}
+
+/// Parse a `#rrggbb` color (the `#` is optional), or `None` if it is malformed.
+pub fn try_parse_hex_color(s: &str) -> Option<Rgb<u8>> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+    if s.len() != 6 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&s[i..i + 2], 16).ok();
+    Some(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
+
+/// Format a color as `#rrggbb`, the inverse of `parse_hex_color`.
+pub fn format_hex_color(color: Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
 /// Linearly interpolate between two colors.
-fn lerp_color(from: &Rgb<u8>, to: &Rgb<u8>, t: f32) -> Rgb<u8> {
+pub fn lerp_color(from: &Rgb<u8>, to: &Rgb<u8>, t: f32) -> Rgb<u8> {
     let r = from[0] as f32 + (to[0] as f32 - from[0] as f32) * t;
     let g = from[1] as f32 + (to[1] as f32 - from[1] as f32) * t;
     let b = from[2] as f32 + (to[2] as f32 - from[2] as f32) * t;
     Rgb([r.round() as u8, g.round() as u8, b.round() as u8])
 }
 
-#[allow(dead_code)] // NOTE: This is not dead, compiler issue
+/// Position of cell `(x, y)` along the diagonal color gradient, from 0 (top left) to 1.
+pub fn gradient_t(x: usize, y: usize, width: usize, height: usize) -> f32 {
+    let fx = if width > 1 {
+        x as f32 / (width - 1) as f32
+    } else {
+        0.0
+    };
+    let fy = if height > 1 {
+        y as f32 / (height - 1) as f32
+    } else {
+        0.0
+    };
+    (fx + fy) / 2.0
+}
+
 pub fn save_generations_as_png(
     generations: &[Vec<u8>],
     width: usize,
@@ -37,13 +116,77 @@ pub fn save_generations_as_png(
         fg_from,
         fg_to,
     );
-    let img_width = (width * scale) as u32;
-    let img_height = (height * scale) as u32;
+    save_rgba_buffer_as_png(
+        buffer,
+        (width * scale) as u32,
+        (height * scale) as u32,
+        output_path,
+    );
+}
+
+pub fn save_rgba_buffer_as_png(
+    buffer: Vec<u8>,
+    img_width: u32,
+    img_height: u32,
+    output_path: &str,
+) {
     let img: ImageBuffer<Rgba<u8>, _> = ImageBuffer::from_raw(img_width, img_height, buffer)
         .expect("Failed to create image buffer");
     img.save(output_path).expect("Failed to save PNG");
 }
 
+/// Save an RGBA buffer as a PNG with a `tEXt` chunk of `text` under `keyword`, encoded
+/// with the same settings as `save_rgba_buffer_as_png`.
+pub fn save_rgba_buffer_as_png_with_text(
+    buffer: Vec<u8>,
+    img_width: u32,
+    img_height: u32,
+    output_path: &str,
+    keyword: &str,
+    text: &str,
+) {
+    let png = encode_png(&buffer, img_width, img_height, Some((keyword, text)));
+    std::fs::write(output_path, png).expect("Failed to save PNG");
+}
+
+/// Encode an RGBA buffer as the PNG `save_rgba_buffer_as_png` would write, adding a
+/// `tEXt` chunk of `text` under `keyword` when `metadata` is given.
+pub fn encode_png(
+    buffer: &[u8],
+    img_width: u32,
+    img_height: u32,
+    metadata: Option<(&str, &str)>,
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut encoder = png_encoder(&mut out, img_width, img_height);
+    if let Some((keyword, text)) = metadata {
+        encoder
+            .add_text_chunk(keyword.to_string(), text.to_string())
+            .expect("Failed to encode PNG");
+    }
+    let mut writer = encoder.write_header().expect("Failed to encode PNG");
+    writer
+        .write_image_data(buffer)
+        .expect("Failed to encode PNG");
+    writer.finish().expect("Failed to encode PNG");
+    out
+}
+
+/// RGBA PNG encoder with the `image` crate's default settings, so its output matches
+/// `save_rgba_buffer_as_png`.
+fn png_encoder(
+    out: &mut Vec<u8>,
+    img_width: u32,
+    img_height: u32,
+) -> png::Encoder<'_, &mut Vec<u8>> {
+    let mut encoder = png::Encoder::new(out, img_width, img_height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Fast);
+    encoder.set_filter(png::Filter::Adaptive);
+    encoder
+}
+
 /// Generate an RGBA buffer for the automaton generations (for WASM canvas rendering).
 pub fn generations_to_rgba_buffer(
     generations: &[Vec<u8>],
@@ -58,224 +201,439 @@ pub fn generations_to_rgba_buffer(
     alive_from: Rgb<u8>,
     alive_to: Rgb<u8>,
 ) -> Vec<u8> {
-    let img_width = (width * scale) as u32;
-    let img_height = (height * scale) as u32;
-    let mut buffer = vec![0u8; (img_width * img_height * 4) as usize];
+    let mut renderer = RgbaRenderer::new(
+        Vec::new(),
+        width,
+        height,
+        scale,
+        CellShape::parse(alive_shape),
+        CellShape::parse(dead_shape),
+        use_links,
+        dead_from,
+        dead_to,
+        alive_from,
+        alive_to,
+    );
+    for gen in generations {
+        renderer.push_row(gen);
+    }
+    renderer.into_buffer()
+}
 
-    for (y, gen) in generations.iter().enumerate() {
-        for (x, &cell) in gen.iter().enumerate() {
-            let fx = if width > 1 {
-                x as f32 / (width - 1) as f32
-            } else {
-                0.0
-            };
-            let fy = if height > 1 {
-                y as f32 / (height - 1) as f32
-            } else {
-                0.0
-            };
-            let t = (fx + fy) / 2.0;
+/// Draws generations into an RGBA buffer one at a time, so large images can be
+/// rendered in bands and shown as they progress.
+///
+/// The result is identical to drawing all cells and then all links: links between
+/// generations `y` and `y + 1` are drawn as soon as `y + 1` is pushed, and only touch
+/// those two generations.
+#[derive(Debug, Clone)]
+pub struct RgbaRenderer {
+    buffer: Vec<u8>,
+    width: usize,
+    height: usize,
+    scale: usize,
+    alive_shape: CellShape,
+    dead_shape: CellShape,
+    use_links: bool,
+    dead_from: Rgb<u8>,
+    dead_to: Rgb<u8>,
+    alive_from: Rgb<u8>,
+    alive_to: Rgb<u8>,
+    /// The last generation pushed, needed for its links to the next one.
+    previous: Vec<u8>,
+    rows: usize,
+}
+
+impl RgbaRenderer {
+    /// A renderer for `height` generations of `width` cells, drawing into `buffer`
+    /// (cleared and resized, so a buffer from `take_buffer` can be reused).
+    pub fn new(
+        mut buffer: Vec<u8>,
+        width: usize,
+        height: usize,
+        scale: usize,
+        alive_shape: CellShape,
+        dead_shape: CellShape,
+        use_links: bool,
+        dead_from: Rgb<u8>,
+        dead_to: Rgb<u8>,
+        alive_from: Rgb<u8>,
+        alive_to: Rgb<u8>,
+    ) -> Self {
+        buffer.clear();
+        buffer.resize(width * scale * height * scale * 4, 0);
+        RgbaRenderer {
+            buffer,
+            width,
+            height,
+            scale,
+            alive_shape,
+            dead_shape,
+            use_links,
+            dead_from,
+            dead_to,
+            alive_from,
+            alive_to,
+            previous: Vec::new(),
+            rows: 0,
+        }
+    }
+
+    /// Draw the next generation.
+    pub fn push_row(&mut self, row: &[u8]) {
+        assert!(self.rows < self.height, "All generations are already drawn");
+        self.draw_row(row, self.rows, self.rows);
+        self.rows += 1;
+    }
+
+    /// Draw generation number `generation` below the others, scrolling them up by one
+    /// once all `height` rows are in use, for endless playback. The colour gradient
+    /// repeats every `height` generations.
+    pub fn scroll_row(&mut self, row: &[u8], generation: usize) {
+        let gradient_y = generation % self.height;
+        if self.rows < self.height {
+            self.draw_row(row, self.rows, gradient_y);
+            self.rows += 1;
+            return;
+        }
+        let row_bytes = self.width * self.scale * self.scale * 4;
+        self.buffer.copy_within(row_bytes.., 0);
+        let len = self.buffer.len();
+        self.buffer[len - row_bytes..].fill(0);
+        self.draw_row(row, self.height - 1, gradient_y);
+    }
+
+    /// Draw `row` at generation `y` with the colours of generation `gradient_y`, linking
+    /// it to the previous row drawn.
+    fn draw_row(&mut self, row: &[u8], y: usize, gradient_y: usize) {
+        for (x, &cell) in row.iter().enumerate() {
+            let t = gradient_t(x, gradient_y, self.width, self.height);
             let (color, shape) = if cell == 1 {
-                (lerp_color(&alive_from, &alive_to, t), alive_shape)
+                (
+                    lerp_color(&self.alive_from, &self.alive_to, t),
+                    self.alive_shape,
+                )
             } else {
-                (lerp_color(&dead_from, &dead_to, t), dead_shape)
+                (
+                    lerp_color(&self.dead_from, &self.dead_to, t),
+                    self.dead_shape,
+                )
             };
 
-            match shape {
-                "circle" => {
-                    let radius = scale as f32 * 0.5;
-                    let center_x = x as f32 * scale as f32 + radius;
-                    let center_y = y as f32 * scale as f32 + radius;
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            let px = x as f32 * scale as f32 + dx as f32 + 0.5;
-                            let py = y as f32 * scale as f32 + dy as f32 + 0.5;
-                            let dist = ((px - center_x).powi(2) + (py - center_y).powi(2)).sqrt();
-                            if dist <= radius {
-                                let idx = (((y * scale + dy) * (width * scale) + (x * scale + dx))
-                                    * 4) as usize;
-                                buffer[idx] = color[0];
-                                buffer[idx + 1] = color[1];
-                                buffer[idx + 2] = color[2];
-                                buffer[idx + 3] = 255;
-                            }
-                        }
+            draw_cell(&mut self.buffer, self.width, x, y, self.scale, shape, color);
+        }
+        if self.use_links && y > 0 {
+            let mut segments = Vec::new();
+            push_row_link_segments(
+                &mut segments,
+                &self.previous,
+                row,
+                y - 1,
+                (gradient_y + self.height - 1) % self.height,
+                self.width,
+                self.height,
+                self.scale,
+                self.alive_from,
+                self.alive_to,
+                self.dead_from,
+                self.dead_to,
+            );
+            let thickness = link_thickness(self.scale);
+            let img_width = (self.width * self.scale) as i32;
+            let img_height = (self.height * self.scale) as i32;
+            for (x0, y0, x1, y1, color) in segments {
+                draw_line_bresenham_rgba(
+                    &mut self.buffer,
+                    img_width,
+                    img_height,
+                    x0,
+                    y0,
+                    x1,
+                    y1,
+                    color,
+                    thickness,
+                );
+            }
+        }
+        if self.use_links {
+            self.previous.clear();
+            self.previous.extend_from_slice(row);
+        }
+    }
+
+    /// Number of generations drawn so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of generations whose pixels are final. With links, the last generation
+    /// drawn still changes when the links to the next one are added.
+    pub fn finished_rows(&self) -> usize {
+        if self.use_links && self.rows < self.height {
+            self.rows.saturating_sub(1)
+        } else {
+            self.rows
+        }
+    }
+
+    /// The RGBA pixels drawn so far; undrawn generations are transparent.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub fn into_buffer(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    /// Move the buffer out, leaving the renderer empty.
+    pub fn take_buffer(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer)
+    }
+}
+
+/// Draw a single cell with the given shape into an RGBA buffer that is `width` cells wide.
+pub(crate) fn draw_cell(
+    buffer: &mut [u8],
+    width: usize,
+    x: usize,
+    y: usize,
+    scale: usize,
+    shape: CellShape,
+    color: Rgb<u8>,
+) {
+    match shape {
+        CellShape::Circle => {
+            let radius = scale as f32 * 0.5;
+            let center_x = x as f32 * scale as f32 + radius;
+            let center_y = y as f32 * scale as f32 + radius;
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let px = x as f32 * scale as f32 + dx as f32 + 0.5;
+                    let py = y as f32 * scale as f32 + dy as f32 + 0.5;
+                    let dist = ((px - center_x).powi(2) + (py - center_y).powi(2)).sqrt();
+                    if dist <= radius {
+                        let idx = ((y * scale + dy) * (width * scale) + (x * scale + dx)) * 4;
+                        buffer[idx] = color[0];
+                        buffer[idx + 1] = color[1];
+                        buffer[idx + 2] = color[2];
+                        buffer[idx + 3] = 255;
                     }
                 }
-                "circle-small" => {
-                    let radius = scale as f32 * 0.25;
-                    let center_x = x as f32 * scale as f32 + scale as f32 * 0.5;
-                    let center_y = y as f32 * scale as f32 + scale as f32 * 0.5;
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            let px = x as f32 * scale as f32 + dx as f32 + 0.5;
-                            let py = y as f32 * scale as f32 + dy as f32 + 0.5;
-                            let dist = ((px - center_x).powi(2) + (py - center_y).powi(2)).sqrt();
-                            if dist <= radius {
-                                let idx = (((y * scale + dy) * (width * scale) + (x * scale + dx))
-                                    * 4) as usize;
-                                buffer[idx] = color[0];
-                                buffer[idx + 1] = color[1];
-                                buffer[idx + 2] = color[2];
-                                buffer[idx + 3] = 255;
-                            }
-                        }
+            }
+        }
+        CellShape::CircleSmall => {
+            let radius = scale as f32 * 0.25;
+            let center_x = x as f32 * scale as f32 + scale as f32 * 0.5;
+            let center_y = y as f32 * scale as f32 + scale as f32 * 0.5;
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let px = x as f32 * scale as f32 + dx as f32 + 0.5;
+                    let py = y as f32 * scale as f32 + dy as f32 + 0.5;
+                    let dist = ((px - center_x).powi(2) + (py - center_y).powi(2)).sqrt();
+                    if dist <= radius {
+                        let idx = ((y * scale + dy) * (width * scale) + (x * scale + dx)) * 4;
+                        buffer[idx] = color[0];
+                        buffer[idx + 1] = color[1];
+                        buffer[idx + 2] = color[2];
+                        buffer[idx + 3] = 255;
                     }
                 }
-                "triangle-up" | "triangle-r-up" => {
-                    for dy in 0..scale {
-                        let row_width = ((dy as f32 / scale as f32) * scale as f32).ceil() as usize;
-                        let x_start = x * scale + (scale - row_width) / 2;
-                        let x_end = x_start + row_width;
-                        for dx in x_start..x_end {
-                            let idx = (((y * scale + dy) * (width * scale) + dx) * 4) as usize;
-                            buffer[idx] = color[0];
-                            buffer[idx + 1] = color[1];
-                            buffer[idx + 2] = color[2];
-                            buffer[idx + 3] = 255;
-                        }
-                    }
+            }
+        }
+        CellShape::TriangleUp => {
+            for dy in 0..scale {
+                let row_width = ((dy as f32 / scale as f32) * scale as f32).ceil() as usize;
+                let x_start = x * scale + (scale - row_width) / 2;
+                let x_end = x_start + row_width;
+                for dx in x_start..x_end {
+                    let idx = ((y * scale + dy) * (width * scale) + dx) * 4;
+                    buffer[idx] = color[0];
+                    buffer[idx + 1] = color[1];
+                    buffer[idx + 2] = color[2];
+                    buffer[idx + 3] = 255;
                 }
-                "triangle-down" | "triangle-r-down" => {
-                    for dy in 0..scale {
-                        let row_width = (((scale - dy - 1) as f32 / scale as f32) * scale as f32)
-                            .ceil() as usize;
-                        let x_start = x * scale + (scale - row_width) / 2;
-                        let x_end = x_start + row_width;
-                        for dx in x_start..x_end {
-                            let idx = (((y * scale + dy) * (width * scale) + dx) * 4) as usize;
-                            buffer[idx] = color[0];
-                            buffer[idx + 1] = color[1];
-                            buffer[idx + 2] = color[2];
-                            buffer[idx + 3] = 255;
-                        }
-                    }
+            }
+        }
+        CellShape::TriangleDown => {
+            for dy in 0..scale {
+                let row_width =
+                    (((scale - dy - 1) as f32 / scale as f32) * scale as f32).ceil() as usize;
+                let x_start = x * scale + (scale - row_width) / 2;
+                let x_end = x_start + row_width;
+                for dx in x_start..x_end {
+                    let idx = ((y * scale + dy) * (width * scale) + dx) * 4;
+                    buffer[idx] = color[0];
+                    buffer[idx + 1] = color[1];
+                    buffer[idx + 2] = color[2];
+                    buffer[idx + 3] = 255;
                 }
-                "triangle-left" => {
-                    for dx in 0..scale {
-                        let col_height =
-                            ((dx as f32 / scale as f32) * scale as f32).ceil() as usize;
-                        let y_start = y * scale + (scale - col_height) / 2;
-                        for dy in y_start..(y_start + col_height) {
-                            let idx = (((dy) * (width * scale) + (x * scale + dx)) * 4) as usize;
-                            buffer[idx] = color[0];
-                            buffer[idx + 1] = color[1];
-                            buffer[idx + 2] = color[2];
-                            buffer[idx + 3] = 255;
-                        }
-                    }
+            }
+        }
+        CellShape::TriangleLeft => {
+            for dx in 0..scale {
+                let col_height = ((dx as f32 / scale as f32) * scale as f32).ceil() as usize;
+                let y_start = y * scale + (scale - col_height) / 2;
+                for dy in y_start..(y_start + col_height) {
+                    let idx = ((dy) * (width * scale) + (x * scale + dx)) * 4;
+                    buffer[idx] = color[0];
+                    buffer[idx + 1] = color[1];
+                    buffer[idx + 2] = color[2];
+                    buffer[idx + 3] = 255;
                 }
-                "triangle-right" => {
-                    for dx in 0..scale {
-                        let col_height = (((scale - dx - 1) as f32 / scale as f32) * scale as f32)
-                            .ceil() as usize;
-                        let y_start = y * scale + (scale - col_height) / 2;
-                        for dy in y_start..(y_start + col_height) {
-                            let idx = (((dy) * (width * scale) + (x * scale + dx)) * 4) as usize;
-                            buffer[idx] = color[0];
-                            buffer[idx + 1] = color[1];
-                            buffer[idx + 2] = color[2];
-                            buffer[idx + 3] = 255;
-                        }
-                    }
+            }
+        }
+        CellShape::TriangleRight => {
+            for dx in 0..scale {
+                let col_height =
+                    (((scale - dx - 1) as f32 / scale as f32) * scale as f32).ceil() as usize;
+                let y_start = y * scale + (scale - col_height) / 2;
+                for dy in y_start..(y_start + col_height) {
+                    let idx = ((dy) * (width * scale) + (x * scale + dx)) * 4;
+                    buffer[idx] = color[0];
+                    buffer[idx + 1] = color[1];
+                    buffer[idx + 2] = color[2];
+                    buffer[idx + 3] = 255;
                 }
-                "triangle-r-a" => {
-                    // Right angle at bottom-left (0, scale-1)
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            if dx <= dy {
-                                let idx = (((y * scale + dy) * (width * scale) + (x * scale + dx))
-                                    * 4) as usize;
-                                buffer[idx] = color[0];
-                                buffer[idx + 1] = color[1];
-                                buffer[idx + 2] = color[2];
-                                buffer[idx + 3] = 255;
-                            }
-                        }
+            }
+        }
+        CellShape::TriangleRA => {
+            // Right angle at bottom-left (0, scale-1)
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    if dx <= dy {
+                        let idx = ((y * scale + dy) * (width * scale) + (x * scale + dx)) * 4;
+                        buffer[idx] = color[0];
+                        buffer[idx + 1] = color[1];
+                        buffer[idx + 2] = color[2];
+                        buffer[idx + 3] = 255;
                     }
                 }
-                "triangle-r-b" => {
-                    // Right angle at bottom-right (scale-1, scale-1)
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            if dx >= scale - dy - 1 {
-                                let idx = (((y * scale + dy) * (width * scale) + (x * scale + dx))
-                                    * 4) as usize;
-                                buffer[idx] = color[0];
-                                buffer[idx + 1] = color[1];
-                                buffer[idx + 2] = color[2];
-                                buffer[idx + 3] = 255;
-                            }
-                        }
+            }
+        }
+        CellShape::TriangleRB => {
+            // Right angle at bottom-right (scale-1, scale-1)
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    if dx >= scale - dy - 1 {
+                        let idx = ((y * scale + dy) * (width * scale) + (x * scale + dx)) * 4;
+                        buffer[idx] = color[0];
+                        buffer[idx + 1] = color[1];
+                        buffer[idx + 2] = color[2];
+                        buffer[idx + 3] = 255;
                     }
                 }
-                "triangle-r-c" => {
-                    // Right angle at top-left (0, 0)
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            if dx >= dy {
-                                let idx = (((y * scale + dy) * (width * scale) + (x * scale + dx))
-                                    * 4) as usize;
-                                buffer[idx] = color[0];
-                                buffer[idx + 1] = color[1];
-                                buffer[idx + 2] = color[2];
-                                buffer[idx + 3] = 255;
-                            }
-                        }
+            }
+        }
+        CellShape::TriangleRC => {
+            // Right angle at top-left (0, 0)
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    if dx >= dy {
+                        let idx = ((y * scale + dy) * (width * scale) + (x * scale + dx)) * 4;
+                        buffer[idx] = color[0];
+                        buffer[idx + 1] = color[1];
+                        buffer[idx + 2] = color[2];
+                        buffer[idx + 3] = 255;
                     }
                 }
-                "triangle-r-d" => {
-                    // Right angle at top-right (scale-1, 0)
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            if dx <= scale - dy - 1 {
-                                let idx = (((y * scale + dy) * (width * scale) + (x * scale + dx))
-                                    * 4) as usize;
-                                buffer[idx] = color[0];
-                                buffer[idx + 1] = color[1];
-                                buffer[idx + 2] = color[2];
-                                buffer[idx + 3] = 255;
-                            }
-                        }
+            }
+        }
+        CellShape::TriangleRD => {
+            // Right angle at top-right (scale-1, 0)
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    if dx < scale - dy {
+                        let idx = ((y * scale + dy) * (width * scale) + (x * scale + dx)) * 4;
+                        buffer[idx] = color[0];
+                        buffer[idx + 1] = color[1];
+                        buffer[idx + 2] = color[2];
+                        buffer[idx + 3] = 255;
                     }
                 }
-                _ => {
-                    // default: square
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            let idx = (((y * scale + dy) * (width * scale) + (x * scale + dx)) * 4)
-                                as usize;
-                            buffer[idx] = color[0];
-                            buffer[idx + 1] = color[1];
-                            buffer[idx + 2] = color[2];
-                            buffer[idx + 3] = 255;
-                        }
+            }
+        }
+        CellShape::Square => {
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let idx = ((y * scale + dy) * (width * scale) + (x * scale + dx)) * 4;
+                    buffer[idx] = color[0];
+                    buffer[idx + 1] = color[1];
+                    buffer[idx + 2] = color[2];
+                    buffer[idx + 3] = 255;
+                }
+            }
+        }
+    }
+}
+
+/// Blend `color` at 50% over every cell set in `mask` (e.g. the damage of a flipped cell).
+pub fn overlay_cells_rgba(
+    buffer: &mut [u8],
+    mask: &[Vec<u8>],
+    width: usize,
+    scale: usize,
+    color: Rgb<u8>,
+) {
+    for (y, row) in mask.iter().enumerate() {
+        for (x, &cell) in row.iter().enumerate() {
+            if cell == 0 {
+                continue;
+            }
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let idx = ((y * scale + dy) * (width * scale) + (x * scale + dx)) * 4;
+                    for c in 0..3 {
+                        buffer[idx + c] = ((buffer[idx + c] as u16 + color[c] as u16) / 2) as u8;
                     }
+                    buffer[idx + 3] = 255;
                 }
             }
         }
     }
-    // Draw links if requested (post-processing)
-    if use_links {
-        draw_links_bresenham_rgba(
-            &mut buffer,
-            generations,
+}
+
+/// A link from one cell centre to another, in pixels, with its color.
+type LinkSegment = (i32, i32, i32, i32, Rgb<u8>);
+
+/// Links between each cell and the neighbours in the next generation with the same state.
+fn link_segments(
+    generations: &[Vec<u8>],
+    width: usize,
+    height: usize,
+    scale: usize,
+    fg_from: Rgb<u8>,
+    fg_to: Rgb<u8>,
+    bg_from: Rgb<u8>,
+    bg_to: Rgb<u8>,
+) -> Vec<LinkSegment> {
+    let mut segments = Vec::new();
+    for y in 0..height - 1 {
+        push_row_link_segments(
+            &mut segments,
+            &generations[y],
+            &generations[y + 1],
+            y,
+            y,
             width,
             height,
             scale,
-            alive_from,
-            alive_to,
-            dead_from,
-            dead_to,
+            fg_from,
+            fg_to,
+            bg_from,
+            bg_to,
         );
     }
-    buffer
+    segments
 }
 
-/// Draw links between neighboring "on" cells in an RGBA buffer using Bresenham's algorithm
-fn draw_links_bresenham_rgba(
-    buffer: &mut [u8],
-    generations: &[Vec<u8>],
+/// Append the links between generation `y` (`row`) and generation `y + 1` (`next`),
+/// coloured as generation `gradient_y` of the gradient.
+fn push_row_link_segments(
+    segments: &mut Vec<LinkSegment>,
+    row: &[u8],
+    next: &[u8],
+    y: usize,
+    gradient_y: usize,
     width: usize,
     height: usize,
     scale: usize,
@@ -284,78 +642,42 @@ fn draw_links_bresenham_rgba(
     bg_from: Rgb<u8>,
     bg_to: Rgb<u8>,
 ) {
-    let thickness = ((scale as i32) / 8).max(1);
-    let img_width = (width * scale) as i32;
-    let img_height = (height * scale) as i32;
-    let neighbor_offsets = [
-        (-1, -1),
-        (0, -1),
-        (1, -1),
-        (-1, 0),
-        (1, 0),
-        (-1, 1),
-        (0, 1),
-        (1, 1),
-    ];
-    for y in 0..height - 1 {
-        for x in 0..width {
-            let cell_val = generations[y][x];
-            let fx = if width > 1 {
-                x as f32 / (width - 1) as f32
-            } else {
-                0.0
-            };
-            let fy = if height > 1 {
-                y as f32 / (height - 1) as f32
-            } else {
-                0.0
-            };
-            let t = (fx + fy) / 2.0;
-            let (cx, cy) = (
-                (x as i32 * scale as i32 + scale as i32 / 2),
-                (y as i32 * scale as i32 + scale as i32 / 2),
-            );
-            for &(dx, dy) in &neighbor_offsets {
-                let nx = x as isize + dx;
-                let ny = y as isize + dy;
-                if ny == y as isize + 1
-                    && nx >= 0
-                    && nx < width as isize
-                    && ny >= 0
-                    && ny < height as isize
-                {
-                    let neighbor_val = generations[ny as usize][nx as usize];
-                    if neighbor_val == cell_val {
-                        let (ncx, ncy) = (
-                            (nx as i32 * scale as i32 + scale as i32 / 2),
-                            (ny as i32 * scale as i32 + scale as i32 / 2),
-                        );
-                        let debug_color = if cell_val == 1 {
-                            // Invert FG gradient for alive links
-                            lerp_color(&fg_to, &fg_from, t)
-                        } else {
-                            // Invert BG gradient for dead links
-                            lerp_color(&bg_to, &bg_from, t)
-                        };
-                        draw_line_bresenham_rgba(
-                            buffer,
-                            img_width,
-                            img_height,
-                            cx,
-                            cy,
-                            ncx,
-                            ncy,
-                            debug_color,
-                            thickness,
-                        );
-                    }
+    for (x, &cell_val) in row.iter().enumerate().take(width) {
+        let t = gradient_t(x, gradient_y, width, height);
+        let (cx, cy) = (
+            (x as i32 * scale as i32 + scale as i32 / 2),
+            (y as i32 * scale as i32 + scale as i32 / 2),
+        );
+        for dx in -1..=1 {
+            let nx = x as isize + dx;
+            let ny = y + 1;
+            if nx >= 0 && nx < width as isize {
+                let neighbor_val = next[nx as usize];
+                if neighbor_val == cell_val {
+                    let (ncx, ncy) = (
+                        (nx as i32 * scale as i32 + scale as i32 / 2),
+                        (ny as i32 * scale as i32 + scale as i32 / 2),
+                    );
+                    let color = if cell_val == 1 {
+                        // Invert FG gradient for alive links
+                        lerp_color(&fg_to, &fg_from, t)
+                    } else {
+                        // Invert BG gradient for dead links
+                        lerp_color(&bg_to, &bg_from, t)
+                    };
+                    segments.push((cx, cy, ncx, ncy, color));
                 }
             }
         }
     }
 }
 
-fn draw_line_bresenham_rgba(
+/// Width of link lines in pixels.
+fn link_thickness(scale: usize) -> i32 {
+    ((scale as i32) / 8).max(1)
+}
+
+pub(crate) fn draw_line_bresenham_rgba(
     buffer: &mut [u8],
     img_width: i32,
     img_height: i32,
@@ -403,3 +725,531 @@ fn draw_line_bresenham_rgba(
         }
     }
 }
+
+/// Width of the rule icon in cells: eight 3-cell panels separated by one empty cell.
+pub const RULE_ICON_WIDTH: usize = 8 * 3 + 7;
+/// Height of the rule icon in cells: the neighbourhood row and the output row.
+pub const RULE_ICON_HEIGHT: usize = 2;
+
+/// Cells of the classic rule icon, `None` for the gaps.
+///
+/// Panels run from neighbourhood `111` on the left to `000` on the right, each with
+/// its output cell under the centre cell.
+pub fn rule_icon_cells(rule: u8) -> Vec<Vec<Option<u8>>> {
+    let mut cells = vec![vec![None; RULE_ICON_WIDTH]; RULE_ICON_HEIGHT];
+    for panel in 0..8 {
+        let idx = 7 - panel;
+        let left = panel * 4;
+        for i in 0..3 {
+            cells[0][left + i] = Some((idx >> (2 - i)) as u8 & 1);
+        }
+        cells[1][left + 1] = Some((rule >> idx) & 1);
+    }
+    cells
+}
+
+/// Render the rule icon to an RGBA buffer of `RULE_ICON_WIDTH * scale` by
+/// `RULE_ICON_HEIGHT * scale` pixels, with transparent gaps.
+pub fn rule_icon_to_rgba_buffer(
+    rule: u8,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    dead_from: Rgb<u8>,
+    dead_to: Rgb<u8>,
+    alive_from: Rgb<u8>,
+    alive_to: Rgb<u8>,
+) -> Vec<u8> {
+    let alive_shape = CellShape::parse(alive_shape);
+    let dead_shape = CellShape::parse(dead_shape);
+    let mut buffer = vec![0u8; RULE_ICON_WIDTH * scale * RULE_ICON_HEIGHT * scale * 4];
+    for (y, row) in rule_icon_cells(rule).iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let Some(cell) = *cell else { continue };
+            let t = gradient_t(x, y, RULE_ICON_WIDTH, RULE_ICON_HEIGHT);
+            let (color, shape) = if cell == 1 {
+                (lerp_color(&alive_from, &alive_to, t), alive_shape)
+            } else {
+                (lerp_color(&dead_from, &dead_to, t), dead_shape)
+            };
+            draw_cell(&mut buffer, RULE_ICON_WIDTH, x, y, scale, shape, color);
+        }
+    }
+    buffer
+}
+
+/// Stack two RGBA buffers vertically, centred horizontally, with `gap` transparent rows
+/// between them. Returns the buffer and its size in pixels.
+pub fn stack_rgba_buffers(
+    top: &[u8],
+    top_width: usize,
+    bottom: &[u8],
+    bottom_width: usize,
+    gap: usize,
+) -> (Vec<u8>, usize, usize) {
+    let top_height = top.len() / (top_width * 4).max(1);
+    let bottom_height = bottom.len() / (bottom_width * 4).max(1);
+    let width = top_width.max(bottom_width);
+    let height = top_height + gap + bottom_height;
+    let mut buffer = vec![0u8; width * height * 4];
+    for (src, src_width, y_offset) in [
+        (top, top_width, 0),
+        (bottom, bottom_width, top_height + gap),
+    ] {
+        let x_offset = (width - src_width) / 2;
+        for (y, row) in src.chunks(src_width * 4).enumerate() {
+            let start = ((y + y_offset) * width + x_offset) * 4;
+            buffer[start..start + row.len()].copy_from_slice(row);
+        }
+    }
+    (buffer, width, height)
+}
+
+/// SVG element for one cell whose top-left corner is at `(px, py)`.
+fn svg_cell(
+    out: &mut String,
+    px: usize,
+    py: usize,
+    scale: usize,
+    shape: CellShape,
+    color: Rgb<u8>,
+) {
+    let fill = format_hex_color(color);
+    let (x0, y0, s) = (px as f32, py as f32, scale as f32);
+    let (x1, y1, xm, ym) = (x0 + s, y0 + s, x0 + s / 2.0, y0 + s / 2.0);
+    let triangle = |points: [(f32, f32); 3]| {
+        let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        format!(
+            "<polygon points=\"{}\" fill=\"{}\"/>\n",
+            points.join(" "),
+            fill
+        )
+    };
+    let element = match shape {
+        CellShape::Square => format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" shape-rendering=\"crispEdges\"/>\n",
+            x0, y0, s, s, fill
+        ),
+        CellShape::Circle | CellShape::CircleSmall => {
+            let r = if shape == CellShape::Circle {
+                s / 2.0
+            } else {
+                s / 4.0
+            };
+            format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+                xm, ym, r, fill
+            )
+        }
+        CellShape::TriangleUp => triangle([(xm, y0), (x1, y1), (x0, y1)]),
+        CellShape::TriangleDown => triangle([(x0, y0), (x1, y0), (xm, y1)]),
+        CellShape::TriangleLeft => triangle([(x0, ym), (x1, y0), (x1, y1)]),
+        CellShape::TriangleRight => triangle([(x0, y0), (x1, ym), (x0, y1)]),
+        CellShape::TriangleRA => triangle([(x0, y0), (x0, y1), (x1, y1)]),
+        CellShape::TriangleRB => triangle([(x1, y0), (x1, y1), (x0, y1)]),
+        CellShape::TriangleRC => triangle([(x0, y0), (x1, y0), (x1, y1)]),
+        CellShape::TriangleRD => triangle([(x0, y0), (x1, y0), (x0, y1)]),
+    };
+    out.push_str(&element);
+}
+
+/// SVG elements for the automaton generations, with the top-left cell at `(x_offset, y_offset)` pixels.
+pub fn generations_to_svg_elements(
+    generations: &[Vec<u8>],
+    width: usize,
+    height: usize,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    dead_from: Rgb<u8>,
+    dead_to: Rgb<u8>,
+    alive_from: Rgb<u8>,
+    alive_to: Rgb<u8>,
+    x_offset: usize,
+    y_offset: usize,
+) -> String {
+    let alive_shape = CellShape::parse(alive_shape);
+    let dead_shape = CellShape::parse(dead_shape);
+    let mut out = String::new();
+    for (y, gen) in generations.iter().enumerate() {
+        for (x, &cell) in gen.iter().enumerate() {
+            let t = gradient_t(x, y, width, height);
+            let (color, shape) = if cell == 1 {
+                (lerp_color(&alive_from, &alive_to, t), alive_shape)
+            } else {
+                (lerp_color(&dead_from, &dead_to, t), dead_shape)
+            };
+            svg_cell(
+                &mut out,
+                x_offset + x * scale,
+                y_offset + y * scale,
+                scale,
+                shape,
+                color,
+            );
+        }
+    }
+    if use_links {
+        let thickness = link_thickness(scale);
+        let segments = link_segments(
+            generations,
+            width,
+            height,
+            scale,
+            alive_from,
+            alive_to,
+            dead_from,
+            dead_to,
+        );
+        for (x0, y0, x1, y1, color) in segments {
+            out.push_str(&format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"square\"/>\n",
+                x0 + x_offset as i32,
+                y0 + y_offset as i32,
+                x1 + x_offset as i32,
+                y1 + y_offset as i32,
+                format_hex_color(color),
+                thickness
+            ));
+        }
+    }
+    out
+}
+
+/// SVG elements blending `color` at 50% over every cell set in `mask`.
+pub fn overlay_cells_svg_elements(
+    mask: &[Vec<u8>],
+    scale: usize,
+    color: Rgb<u8>,
+    x_offset: usize,
+    y_offset: usize,
+) -> String {
+    let mut out = String::new();
+    for (y, row) in mask.iter().enumerate() {
+        for (x, &cell) in row.iter().enumerate() {
+            if cell == 1 {
+                out.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.5\"/>\n",
+                    x_offset + x * scale,
+                    y_offset + y * scale,
+                    scale,
+                    scale,
+                    format_hex_color(color)
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// SVG elements for the rule icon, with its top-left corner at `(x_offset, y_offset)` pixels.
+pub fn rule_icon_to_svg_elements(
+    rule: u8,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    dead_from: Rgb<u8>,
+    dead_to: Rgb<u8>,
+    alive_from: Rgb<u8>,
+    alive_to: Rgb<u8>,
+    x_offset: usize,
+    y_offset: usize,
+) -> String {
+    let alive_shape = CellShape::parse(alive_shape);
+    let dead_shape = CellShape::parse(dead_shape);
+    let mut out = String::new();
+    for (y, row) in rule_icon_cells(rule).iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let Some(cell) = *cell else { continue };
+            let t = gradient_t(x, y, RULE_ICON_WIDTH, RULE_ICON_HEIGHT);
+            let (color, shape) = if cell == 1 {
+                (lerp_color(&alive_from, &alive_to, t), alive_shape)
+            } else {
+                (lerp_color(&dead_from, &dead_to, t), dead_shape)
+            };
+            svg_cell(
+                &mut out,
+                x_offset + x * scale,
+                y_offset + y * scale,
+                scale,
+                shape,
+                color,
+            );
+        }
+    }
+    out
+}
+
+/// Wrap SVG elements in a document of `img_width` by `img_height` pixels and save it.
+pub fn save_svg(elements: &str, img_width: usize, img_height: usize, output_path: &str) {
+    let svg = svg_document(elements, img_width, img_height, None);
+    std::fs::write(output_path, svg).expect("Failed to save SVG");
+}
+
+/// Wrap SVG elements in a document of `img_width` by `img_height` pixels, with `text`
+/// in a `<metadata>` element whose id is `keyword` when `metadata` is given.
+pub fn svg_document(
+    elements: &str,
+    img_width: usize,
+    img_height: usize,
+    metadata: Option<(&str, &str)>,
+) -> String {
+    let metadata = match metadata {
+        Some((keyword, text)) => format!("<metadata id=\"{}\">{}</metadata>\n", keyword, text),
+        None => String::new(),
+    };
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n{}{}</svg>\n",
+        metadata,
+        elements,
+        w = img_width,
+        h = img_height
+    )
+}
+
+/// Animated image formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+/// Encode an animation that draws the diagram `rows_per_frame` generations at a time,
+/// waiting `frame_delay` milliseconds between frames (GIF rounds down to 10 ms) and
+/// looping forever. With `metadata`, `text` is stored under `keyword` in a PNG `tEXt`
+/// chunk or a GIF comment (as `keyword=text`).
+///
+/// The first and last frames cover the whole image; those between only cover the rows
+/// that changed, so each generation is encoded about once.
+pub fn generations_to_animation(
+    generations_vec: &[Vec<u8>],
+    width: usize,
+    height: usize,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    dead_from: Rgb<u8>,
+    dead_to: Rgb<u8>,
+    alive_from: Rgb<u8>,
+    alive_to: Rgb<u8>,
+    rows_per_frame: usize,
+    frame_delay: u16,
+    format: AnimationFormat,
+    metadata: Option<(&str, &str)>,
+) -> Vec<u8> {
+    assert!(
+        rows_per_frame > 0,
+        "Frames must add at least one generation"
+    );
+    let (img_width, img_height) = (width * scale, height * scale);
+    let frame_count = height.div_ceil(rows_per_frame);
+    let mut renderer = RgbaRenderer::new(
+        Vec::new(),
+        width,
+        height,
+        scale,
+        CellShape::parse(alive_shape),
+        CellShape::parse(dead_shape),
+        use_links,
+        dead_from,
+        dead_to,
+        alive_from,
+        alive_to,
+    );
+    let mut out = Vec::new();
+    let mut encoder = match format {
+        AnimationFormat::Gif => AnimationEncoder::gif(&mut out, img_width, img_height, metadata),
+        AnimationFormat::Apng => AnimationEncoder::apng(
+            &mut out,
+            img_width,
+            img_height,
+            frame_count,
+            frame_delay,
+            metadata,
+        ),
+    };
+    let row_bytes = img_width * scale * 4;
+    for (index, rows) in generations_vec[..height].chunks(rows_per_frame).enumerate() {
+        // Rows before `start` are unchanged since the previous frame
+        let start = renderer.finished_rows();
+        for row in rows {
+            renderer.push_row(row);
+        }
+        let last = index + 1 == frame_count;
+        let (start, end) = if index == 0 || last {
+            (0, height)
+        } else {
+            (start, renderer.rows())
+        };
+        encoder.frame(
+            &renderer.buffer()[start * row_bytes..end * row_bytes],
+            start * scale,
+            (end - start) * scale,
+            frame_delay,
+            // Clear the image before the animation loops
+            last && frame_count > 1,
+        );
+    }
+    encoder.finish();
+    out
+}
+
+/// Writes the frames of `generations_to_animation` in either format.
+enum AnimationEncoder<'a> {
+    Gif {
+        encoder: gif::Encoder<&'a mut Vec<u8>>,
+        width: u16,
+    },
+    Apng {
+        writer: png::Writer<&'a mut Vec<u8>>,
+        width: u32,
+    },
+}
+
+impl<'a> AnimationEncoder<'a> {
+    fn gif(
+        out: &'a mut Vec<u8>,
+        width: usize,
+        height: usize,
+        metadata: Option<(&str, &str)>,
+    ) -> Self {
+        let width = u16::try_from(width).expect("GIF images are at most 65535 pixels wide");
+        let height = u16::try_from(height).expect("GIF images are at most 65535 pixels high");
+        let mut encoder = gif::Encoder::new(out, width, height, &[]).expect("Failed to encode GIF");
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .expect("Failed to encode GIF");
+        if let Some((keyword, text)) = metadata {
+            let comment = format!("{}={}", keyword, text);
+            encoder
+                .write_raw_extension(gif::Extension::Comment.into(), &[comment.as_bytes()])
+                .expect("Failed to encode GIF");
+        }
+        AnimationEncoder::Gif { encoder, width }
+    }
+
+    fn apng(
+        out: &'a mut Vec<u8>,
+        width: usize,
+        height: usize,
+        frame_count: usize,
+        frame_delay: u16,
+        metadata: Option<(&str, &str)>,
+    ) -> Self {
+        let mut encoder = png_encoder(out, width as u32, height as u32);
+        encoder
+            .set_animated(frame_count as u32, 0)
+            .expect("Failed to encode PNG");
+        encoder
+            .set_frame_delay(frame_delay, 1000)
+            .expect("Failed to encode PNG");
+        if let Some((keyword, text)) = metadata {
+            encoder
+                .add_text_chunk(keyword.to_string(), text.to_string())
+                .expect("Failed to encode PNG");
+        }
+        AnimationEncoder::Apng {
+            writer: encoder.write_header().expect("Failed to encode PNG"),
+            width: width as u32,
+        }
+    }
+
+    /// Write `pixels`, the RGBA rows from pixel row `y` down `height` rows, as the next
+    /// frame, clearing it again before the following one if `clear`.
+    fn frame(&mut self, pixels: &[u8], y: usize, height: usize, delay: u16, clear: bool) {
+        match self {
+            AnimationEncoder::Gif { encoder, width } => {
+                let mut frame = gif_frame(pixels, *width, height as u16);
+                frame.top = y as u16;
+                frame.delay = delay / 10;
+                frame.dispose = if clear {
+                    gif::DisposalMethod::Background
+                } else {
+                    gif::DisposalMethod::Keep
+                };
+                encoder.write_frame(&frame).expect("Failed to encode GIF");
+            }
+            AnimationEncoder::Apng { writer, width } => {
+                // Frames must stay inside the image after each call
+                writer.reset_frame_position().expect("Failed to encode PNG");
+                writer
+                    .set_frame_dimension(*width, height as u32)
+                    .expect("Failed to encode PNG");
+                writer
+                    .set_frame_position(0, y as u32)
+                    .expect("Failed to encode PNG");
+                writer
+                    .set_dispose_op(if clear {
+                        png::DisposeOp::Background
+                    } else {
+                        png::DisposeOp::None
+                    })
+                    .expect("Failed to encode PNG");
+                writer
+                    .write_image_data(pixels)
+                    .expect("Failed to encode PNG");
+            }
+        }
+    }
+
+    fn finish(self) {
+        match self {
+            AnimationEncoder::Gif { encoder, .. } => {
+                encoder.into_inner().expect("Failed to encode GIF");
+            }
+            AnimationEncoder::Apng { writer, .. } => writer.finish().expect("Failed to encode PNG"),
+        }
+    }
+}
+
+/// A GIF frame of RGBA pixels. Transparent pixels get their own palette entry, and the
+/// opaque colours are reduced to the other 255 with NeuQuant if there are more.
+fn gif_frame(pixels: &[u8], width: u16, height: u16) -> gif::Frame<'static> {
+    const TRANSPARENT: u8 = 255;
+    let mut colors: Vec<[u8; 3]> = pixels
+        .chunks_exact(4)
+        .filter(|p| p[3] != 0)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+    colors.sort_unstable();
+    colors.dedup();
+    let (palette, indices): (Vec<u8>, Vec<u8>) = if colors.len() < 256 {
+        let indices = pixels
+            .chunks_exact(4)
+            .map(|p| match p[3] {
+                0 => TRANSPARENT,
+                _ => colors
+                    .binary_search(&[p[0], p[1], p[2]])
+                    .expect("Colour is in the palette") as u8,
+            })
+            .collect();
+        (colors.concat(), indices)
+    } else {
+        let opaque: Vec<u8> = pixels
+            .chunks_exact(4)
+            .filter(|p| p[3] != 0)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect();
+        let quantizer = color_quant::NeuQuant::new(10, 255, &opaque);
+        let indices = pixels
+            .chunks_exact(4)
+            .map(|p| match p[3] {
+                0 => TRANSPARENT,
+                _ => quantizer.index_of(&[p[0], p[1], p[2], 255]) as u8,
+            })
+            .collect();
+        (quantizer.color_map_rgb(), indices)
+    };
+    let mut palette = palette;
+    palette.resize(256 * 3, 0);
+    gif::Frame {
+        width,
+        height,
+        buffer: indices.into(),
+        palette: Some(palette),
+        transparent: Some(TRANSPARENT),
+        ..gif::Frame::default()
+    }
+}
@@ -1,121 +1,517 @@
-use rand::Rng;
-use wasm_bindgen::prelude::*;
-mod image_output;
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::too_many_arguments)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+// The cdylib built alongside the rlib needs std's panic handler on hosted targets, even
+// when the core itself is `no_std`.
+#[cfg(all(not(feature = "std"), not(target_os = "none")))]
+extern crate std as _;
+
+#[cfg(feature = "alloc")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod classify;
+#[cfg(feature = "std")]
+pub mod damage;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "png")]
+pub mod image_output;
+#[cfg(feature = "png")]
+pub mod permalink;
+#[cfg(feature = "alloc")]
+pub mod preimage;
+#[cfg(feature = "python")]
+mod python;
 mod rng_utils;
-use image::Rgb;
-use rng_utils::seeded_small_rng;
+#[cfg(feature = "std")]
+pub mod rule_table;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "alloc")]
+pub mod symmetry;
+#[cfg(feature = "wasm")]
+mod tiles;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use rng_utils::seeded_rng;
+pub use rng_utils::SeedScheme;
+use rng_utils::{next_unit, SimRng};
+#[cfg(feature = "wasm")]
+pub use wasm::*;
 
-#[wasm_bindgen]
-pub fn run_automaton(
+/// How cells beyond the edges of a row are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// Cells beyond the edges are always dead.
+    Dead,
+    /// The row wraps around into a ring.
+    Periodic,
+}
+
+impl Boundary {
+    /// Parse a boundary name as used by the CLI (`dead` or `periodic`).
+    pub fn parse(s: &str) -> Self {
+        Self::from_name(s).unwrap_or_else(|| panic!("Unknown boundary: {}", s))
+    }
+
+    /// The boundary called `s`, or `None` for an unknown name.
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "dead" => Some(Boundary::Dead),
+            "periodic" => Some(Boundary::Periodic),
+            _ => None,
+        }
+    }
+
+    /// The name accepted by `parse`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Boundary::Dead => "dead",
+            Boundary::Periodic => "periodic",
+        }
+    }
+}
+
+/// Which family of update rules the automaton uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleFamily {
+    /// Standard elementary automaton: the next state depends only on the neighbourhood.
+    Elementary,
+    /// Fredkin's second-order construction: `rule(neighbourhood) XOR previous state`.
+    /// Exactly reversible, see `Automaton::step_back`.
+    SecondOrder,
+}
+
+impl RuleFamily {
+    /// Parse a family name as used by the CLI and WASM (`elementary` or `second-order`).
+    pub fn parse(s: &str) -> Self {
+        Self::from_name(s).unwrap_or_else(|| panic!("Unknown rule family: {}", s))
+    }
+
+    /// The family called `s`, or `None` for an unknown name.
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "elementary" => Some(RuleFamily::Elementary),
+            "second-order" => Some(RuleFamily::SecondOrder),
+            _ => None,
+        }
+    }
+
+    /// The name accepted by `parse`.
+    pub fn name(self) -> &'static str {
+        match self {
+            RuleFamily::Elementary => "elementary",
+            RuleFamily::SecondOrder => "second-order",
+        }
+    }
+}
+
+/// How cells are updated each generation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdateMode {
+    /// Every cell is updated at once from the previous generation.
+    Synchronous,
+    /// α-asynchronous: each cell is updated with probability α, otherwise it keeps its state.
+    Alpha(f64),
+    /// Cells are updated one at a time, in place, in a fresh random order every generation.
+    RandomSequential,
+}
+
+impl UpdateMode {
+    /// Parse an update mode name as used by the CLI (`sync`, `alpha` or `sequential`).
+    pub fn parse(s: &str, alpha: f64) -> Self {
+        match s {
+            "sync" => UpdateMode::Synchronous,
+            "alpha" => UpdateMode::Alpha(alpha),
+            "sequential" => UpdateMode::RandomSequential,
+            _ => panic!("Unknown update mode: {}", s),
+        }
+    }
+}
+
+/// Streaming elementary automaton engine.
+///
+/// Holds only the current and previous generations, so arbitrarily wide or long
+/// runs can be consumed row by row without keeping the full history in memory.
+/// Stochastic options draw from the same seeded RNG as the initial row, so a seed
+/// reproduces a run exactly.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct Automaton {
     rule: u8,
-    random_distribution: Option<f64>,
-    width: usize,
-    generations: usize,
-    seed: Option<u64>,
-) -> Vec<u8> {
-    let mut rng = seeded_small_rng(seed);
-
-    let mut current = vec![0u8; width];
-    if let Some(p) = random_distribution {
-        if p > 0.0 {
-            for cell in current.iter_mut() {
-                // Use next_u32 for randomness, convert to [0,1)
-                let rand_val = (rng.next_u32() as f64) / (u32::MAX as f64 + 1.0);
-                *cell = if rand_val < p { 1 } else { 0 };
+    previous: Vec<u8>,
+    current: Vec<u8>,
+    next: Vec<u8>,
+    generation: usize,
+    boundary: Boundary,
+    family: RuleFamily,
+    rng: SimRng,
+    probabilities: Option<[f64; 8]>,
+    noise: f64,
+    update_mode: UpdateMode,
+}
+
+/// Neighbourhood index `(left << 2) | (center << 1) | right` of cell `i`.
+fn neighbourhood(row: &[u8], i: usize, boundary: Boundary) -> u8 {
+    let width = row.len();
+    let left = if i > 0 {
+        row[i - 1]
+    } else if boundary == Boundary::Periodic {
+        row[width - 1]
+    } else {
+        0
+    };
+    let right = if i + 1 < width {
+        row[i + 1]
+    } else if boundary == Boundary::Periodic {
+        row[0]
+    } else {
+        0
+    };
+    (left << 2) | (row[i] << 1) | right
+}
+
+/// Apply `rule` to every neighbourhood of `row`, writing the result into `out`.
+fn apply_rule(rule: u8, boundary: Boundary, row: &[u8], out: &mut [u8]) {
+    for (i, cell) in out.iter_mut().enumerate() {
+        *cell = (rule >> neighbourhood(row, i, boundary)) & 1;
+    }
+}
+
+/// Fill `row` with cells alive with probability `random_distribution`, or a single
+/// centre cell when the distribution is `None` or not positive. An empty row stays empty.
+fn fill_initial_row(row: &mut [u8], random_distribution: Option<f64>, rng: &mut SimRng) {
+    row.fill(0);
+    match random_distribution {
+        Some(p) if p > 0.0 => {
+            for cell in row.iter_mut() {
+                *cell = if next_unit(rng) < p { 1 } else { 0 };
             }
-        } else {
-            current[width / 2] = 1;
         }
-    } else {
-        current[width / 2] = 1;
-    }
-    let mut generations_vec = Vec::with_capacity(generations * width);
-    generations_vec.extend_from_slice(&current);
-    for _ in 1..generations {
-        let mut next = vec![0u8; width];
-        for i in 0..width {
-            let left = if i == 0 { 0 } else { current[i - 1] };
-            let center = current[i];
-            let right = if i == width - 1 { 0 } else { current[i + 1] };
-            let idx = (left << 2) | (center << 1) | right;
-            next[i] = (rule >> idx) & 1;
+        _ => {
+            if let Some(centre) = row.get_mut(row.len() / 2) {
+                *centre = 1;
+            }
         }
-        generations_vec.extend_from_slice(&next);
-        current = next;
     }
-    generations_vec
 }
 
-/// Helper to parse hex color string (e.g. "#ffaaff") to Rgb<u8>
-fn parse_hex_color(s: &str) -> Rgb<u8> {
-    let s = s.strip_prefix('#').unwrap_or(s);
-    assert!(s.len() == 6, "Color must be in format #RRGGBB");
-    let r = u8::from_str_radix(&s[0..2], 16).expect("Invalid hex color");
-    let g = u8::from_str_radix(&s[2..4], 16).expect("Invalid hex color");
-    let b = u8::from_str_radix(&s[4..6], 16).expect("Invalid hex color");
-    Rgb([r, g, b])
+// Fixed-buffer API: the same rules as `Automaton`, writing into caller-provided rows
+// so it runs without an allocator.
+
+/// Write the initial row that `Automaton::new_with_scheme` would start from into `row`.
+pub fn initial_row(
+    row: &mut [u8],
+    random_distribution: Option<f64>,
+    seed: u64,
+    scheme: SeedScheme,
+) {
+    let mut rng = SimRng::from_u64(seed, scheme);
+    fill_initial_row(row, random_distribution, &mut rng);
+}
+
+/// Write the generation after `row` under an elementary `rule` into `next`.
+pub fn step_row(rule: u8, boundary: Boundary, row: &[u8], next: &mut [u8]) {
+    assert_eq!(row.len(), next.len(), "Rows must have the same width");
+    apply_rule(rule, boundary, row, next);
+}
+
+/// Write the generation after `row` under a second-order `rule` into `next`, where
+/// `previous` is the generation before `row`. Swapping `previous` and `next` steps back.
+pub fn step_row_second_order(
+    rule: u8,
+    boundary: Boundary,
+    previous: &[u8],
+    row: &[u8],
+    next: &mut [u8],
+) {
+    assert!(
+        previous.len() == row.len() && next.len() == row.len(),
+        "Rows must have the same width"
+    );
+    apply_rule(rule, boundary, row, next);
+    for (next, &prev) in next.iter_mut().zip(previous) {
+        *next ^= prev;
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Automaton {
+    /// Create an automaton with a random initial row (probability `random_distribution`)
+    /// or a single centre cell when the distribution is `None` or not positive.
+    pub fn new(
+        rule: u8,
+        random_distribution: Option<f64>,
+        width: usize,
+        seed: Option<u64>,
+    ) -> Self {
+        Self::new_with_scheme(rule, random_distribution, width, seed, SeedScheme::LATEST)
+    }
+
+    /// Like `new`, but expanding the seed with the given scheme, e.g. to reproduce
+    /// a run published before the latest scheme was introduced.
+    pub fn new_with_scheme(
+        rule: u8,
+        random_distribution: Option<f64>,
+        width: usize,
+        seed: Option<u64>,
+        scheme: SeedScheme,
+    ) -> Self {
+        let mut rng = seeded_rng(seed, scheme);
+        let mut current = vec![0u8; width];
+        fill_initial_row(&mut current, random_distribution, &mut rng);
+        let mut automaton = Self::from_row(rule, current);
+        automaton.rng = rng;
+        automaton
+    }
+
+    /// Create an automaton starting from an explicit initial row.
+    ///
+    /// Stochastic updates use an RNG seeded with 0 under the latest seed scheme unless
+    /// `with_seed` or `with_seed_scheme` is called.
+    pub fn from_row(rule: u8, row: Vec<u8>) -> Self {
+        Automaton {
+            rule,
+            previous: vec![0u8; row.len()],
+            next: vec![0u8; row.len()],
+            current: row,
+            generation: 0,
+            boundary: Boundary::Dead,
+            family: RuleFamily::Elementary,
+            rng: seeded_rng(Some(0), SeedScheme::LATEST),
+            probabilities: None,
+            noise: 0.0,
+            update_mode: UpdateMode::Synchronous,
+        }
+    }
+
+    /// Use the given boundary condition instead of dead edges.
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Use the given rule family. Second-order runs start with an all-dead previous row.
+    pub fn with_family(mut self, family: RuleFamily) -> Self {
+        self.family = family;
+        self
+    }
+
+    /// Set the previous row used by second-order rules, e.g. to continue an earlier run.
+    pub fn with_previous(mut self, row: Vec<u8>) -> Self {
+        assert_eq!(
+            row.len(),
+            self.current.len(),
+            "Previous row must match the width"
+        );
+        self.previous = row;
+        self
+    }
+
+    /// Reseed the RNG used by stochastic updates, keeping the current seed scheme.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.rng = seeded_rng(seed, self.seed_scheme());
+        self
+    }
+
+    /// Reseed the RNG used by stochastic updates with the given seed scheme.
+    pub fn with_seed_scheme(mut self, seed: Option<u64>, scheme: SeedScheme) -> Self {
+        self.rng = seeded_rng(seed, scheme);
+        self
+    }
+
+    /// Replace the rule with a probabilistic one: neighbourhood `idx` (0 for `000`
+    /// up to 7 for `111`) produces an alive cell with probability `probabilities[idx]`.
+    pub fn with_probabilities(mut self, probabilities: [f64; 8]) -> Self {
+        self.probabilities = Some(probabilities);
+        self
+    }
+
+    /// Flip each cell's output with probability `noise`.
+    pub fn with_noise(mut self, noise: f64) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Use the given update mode instead of synchronous updates.
+    pub fn with_update_mode(mut self, update_mode: UpdateMode) -> Self {
+        self.update_mode = update_mode;
+        self
+    }
+
+    /// The elementary rule number (ignored when probabilities are set).
+    pub fn rule(&self) -> u8 {
+        self.rule
+    }
+
+    /// The seed scheme of the RNG.
+    pub fn seed_scheme(&self) -> SeedScheme {
+        self.rng.state().0
+    }
+
+    /// The current generation's row of cells.
+    pub fn row(&self) -> &[u8] {
+        &self.current
+    }
+
+    /// Index of the current generation (0 for the initial row).
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Flip the cell at `index` of the current row.
+    pub fn flip_cell(&mut self, index: usize) {
+        assert!(
+            index < self.current.len(),
+            "Flipped cell must be inside the row"
+        );
+        self.current[index] ^= 1;
+    }
+
+    /// Whether any option draws from the RNG while stepping.
+    fn is_stochastic(&self) -> bool {
+        self.probabilities.is_some()
+            || self.noise > 0.0
+            || self.update_mode != UpdateMode::Synchronous
+    }
+
+    /// New state of cell `i` with neighbourhood `idx`, before the second-order XOR.
+    fn cell_output(&mut self, idx: u8) -> u8 {
+        let mut out = match &self.probabilities {
+            Some(p) => (next_unit(&mut self.rng) < p[idx as usize]) as u8,
+            None => (self.rule >> idx) & 1,
+        };
+        if self.noise > 0.0 && next_unit(&mut self.rng) < self.noise {
+            out ^= 1;
+        }
+        out
+    }
+
+    /// Fill `next` using the stochastic options.
+    fn step_stochastic(&mut self) {
+        let width = self.current.len();
+        let second_order = self.family == RuleFamily::SecondOrder;
+        match self.update_mode {
+            UpdateMode::Synchronous | UpdateMode::Alpha(_) => {
+                let alpha = match self.update_mode {
+                    UpdateMode::Alpha(a) => a,
+                    _ => 1.0,
+                };
+                for i in 0..width {
+                    if alpha < 1.0 && next_unit(&mut self.rng) >= alpha {
+                        self.next[i] = self.current[i];
+                        continue;
+                    }
+                    let idx = neighbourhood(&self.current, i, self.boundary);
+                    let out = self.cell_output(idx);
+                    self.next[i] = if second_order {
+                        out ^ self.previous[i]
+                    } else {
+                        out
+                    };
+                }
+            }
+            UpdateMode::RandomSequential => {
+                self.next.copy_from_slice(&self.current);
+                // Fisher-Yates shuffle of the update order
+                let mut order: Vec<usize> = (0..width).collect();
+                for i in (1..width).rev() {
+                    let j = (next_unit(&mut self.rng) * (i + 1) as f64) as usize;
+                    order.swap(i, j);
+                }
+                for i in order {
+                    let idx = neighbourhood(&self.next, i, self.boundary);
+                    let out = self.cell_output(idx);
+                    self.next[i] = if second_order {
+                        out ^ self.previous[i]
+                    } else {
+                        out
+                    };
+                }
+            }
+        }
+    }
+
+    /// Advance the automaton by one generation.
+    pub fn step(&mut self) {
+        if self.is_stochastic() {
+            self.step_stochastic();
+        } else {
+            match self.family {
+                RuleFamily::Elementary => {
+                    step_row(self.rule, self.boundary, &self.current, &mut self.next)
+                }
+                RuleFamily::SecondOrder => step_row_second_order(
+                    self.rule,
+                    self.boundary,
+                    &self.previous,
+                    &self.current,
+                    &mut self.next,
+                ),
+            }
+        }
+        core::mem::swap(&mut self.previous, &mut self.current);
+        core::mem::swap(&mut self.current, &mut self.next);
+        self.generation += 1;
+    }
+
+    /// Step a second-order automaton back by one generation, exactly undoing `step`.
+    pub fn step_back(&mut self) {
+        assert!(
+            self.family == RuleFamily::SecondOrder,
+            "Only second-order automata can be stepped backward"
+        );
+        assert!(
+            !self.is_stochastic(),
+            "Stochastic automata cannot be stepped backward"
+        );
+        assert!(
+            self.generation > 0,
+            "Cannot step back past the initial generation"
+        );
+        // x[t-2] = rule(x[t-1]) XOR x[t]
+        step_row_second_order(
+            self.rule,
+            self.boundary,
+            &self.current,
+            &self.previous,
+            &mut self.next,
+        );
+        core::mem::swap(&mut self.current, &mut self.previous);
+        core::mem::swap(&mut self.previous, &mut self.next);
+        self.generation -= 1;
+    }
+
+    /// Collect `generations` rows, starting with the current one, into a flat vector.
+    pub fn run(&mut self, generations: usize) -> Vec<u8> {
+        let mut generations_vec = Vec::with_capacity(generations * self.current.len());
+        generations_vec.extend_from_slice(&self.current);
+        for _ in 1..generations {
+            self.step();
+            generations_vec.extend_from_slice(&self.current);
+        }
+        generations_vec
+    }
 }
 
-/// WASM-exported: Generate RGBA buffer for automaton image (for canvas rendering)
-#[wasm_bindgen]
-pub fn generate_automaton_image(
+/// `generations` rows of a new automaton as a flat vector, expanding `seed` with the
+/// given seed scheme version (the latest when `None`).
+#[cfg(feature = "alloc")]
+pub fn run_automaton(
     rule: u8,
     random_distribution: Option<f64>,
     width: usize,
     generations: usize,
     seed: Option<u64>,
-    scale: usize,
-    alive_shape: &str,
-    dead_shape: &str,
-    use_links: bool,
-    bg_from: &str,
-    bg_to: &str,
-    fg_from: &str,
-    fg_to: &str,
+    seed_scheme: Option<u8>,
 ) -> Vec<u8> {
-    let mut rng = seeded_small_rng(seed);
-    let mut current = vec![0u8; width];
-    if let Some(p) = random_distribution {
-        if p > 0.0 {
-            for cell in current.iter_mut() {
-                let rand_val = (rng.next_u32() as f64) / (u32::MAX as f64 + 1.0);
-                *cell = if rand_val < p { 1 } else { 0 };
-            }
-        } else {
-            current[width / 2] = 1;
-        }
-    } else {
-        current[width / 2] = 1;
-    }
-    let mut generations_vec = Vec::with_capacity(generations);
-    generations_vec.push(current.clone());
-    for _ in 1..generations {
-        let mut next = vec![0u8; width];
-        for i in 0..width {
-            let left = if i == 0 { 0 } else { current[i - 1] };
-            let center = current[i];
-            let right = if i == width - 1 { 0 } else { current[i + 1] };
-            let idx = (left << 2) | (center << 1) | right;
-            next[i] = (rule >> idx) & 1;
-        }
-        generations_vec.push(next.clone());
-        current = next;
-    }
-    let dead_from = parse_hex_color(bg_from);
-    let dead_to = parse_hex_color(bg_to);
-    let alive_from = parse_hex_color(fg_from);
-    let alive_to = parse_hex_color(fg_to);
-    image_output::generations_to_rgba_buffer(
-        &generations_vec,
-        width,
-        generations,
-        scale,
-        alive_shape,
-        dead_shape,
-        use_links,
-        dead_from,
-        dead_to,
-        alive_from,
-        alive_to,
-    )
+    let scheme = seed_scheme.map_or(SeedScheme::LATEST, |v| {
+        SeedScheme::from_version(v).expect("Seed scheme must be 1, 2 or 3")
+    });
+    Automaton::new_with_scheme(rule, random_distribution, width, seed, scheme).run(generations)
 }
@@ -1,36 +1,154 @@
-use ca::run_automaton;
-use clap::Parser;
-mod image_output;
+#![allow(clippy::too_many_arguments)]
+
+use ca::classify::estimate_class;
+use ca::damage::{damage_growth_rate, damage_spread};
+use ca::export::{read_generations, DataFormat, DataWriter};
+use ca::image_output::{self, format_hex_color, parse_hex_color};
+use ca::permalink::{self, Config};
+use ca::preimage::{backtrack, count_preimages, find_preimages};
+use ca::rule_table;
+use ca::stats::{save_chart, StatsCollector};
+use ca::symmetry;
+use ca::{Automaton, Boundary, RuleFamily, SeedScheme, UpdateMode};
+use clap::{Parser, Subcommand};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use terminal::{terminal_columns, TerminalMode, TerminalRenderer};
+mod terminal;
 
 use image::Rgb;
 
 /// Runs an elementary cellular automaton and prints the generations.
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    /// Rule number (0-255)
-    rule: u8,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Random seed (u64, optional)
+    #[command(flatten)]
+    sim: Option<SimArgs>,
+
+    /// Pretty print generations (1 as '#' and 0 as space)
+    #[arg(long, short = 'p', default_value_t = true)]
+    pretty_print: bool,
+
+    #[command(flatten)]
+    render: RenderArgs,
+
+    #[command(flatten)]
+    terminal: TerminalArgs,
+
+    #[command(flatten)]
+    checkpoint: CheckpointArgs,
+
+    /// Output PNG, SVG or GIF file (or data file with --format)
+    #[arg(long, short = 'o')]
+    output: Option<String>,
+
+    /// Write the generations as data instead of an image: digits, json, csv, pbm, pgm, rle or npy
+    /// (to --output, or stdout)
     #[arg(long)]
-    seed: Option<u64>,
+    format: Option<String>,
 
-    /// Probability for random initial state (0.0-1.0), or 'none' for single center cell
-    #[arg(long, short = 'd', default_value = "none")]
-    random_distribution: String,
+    /// Overlay the damage caused by flipping one initial cell in a third color
+    #[arg(long, default_value_t = false)]
+    damage: bool,
 
-    /// Width of the automaton
-    #[arg(long, short = 'w', default_value_t = 64)]
-    width: usize,
+    /// Index of the initial cell to flip for --damage (defaults to the center)
+    #[arg(long)]
+    damage_cell: Option<usize>,
 
-    /// Number of generations to run
-    #[arg(long, short = 'g', default_value_t = 32)]
-    generations: usize,
+    /// Color for damaged cells
+    #[arg(long, default_value = "#ff0000")]
+    damage_color: String,
+}
 
-    /// Pretty print generations (1 as '#' and 0 as space)
-    #[arg(long, short = 'p', default_value_t = true)]
-    pretty_print: bool,
+/// Where and how often the main run saves its engine state.
+#[derive(clap::Args, Debug)]
+struct CheckpointArgs {
+    /// Save the engine state to this file when the run ends, to continue it with --resume
+    #[arg(long)]
+    checkpoint: Option<String>,
+
+    /// Also save the checkpoint every N generations
+    #[arg(long, requires = "checkpoint")]
+    checkpoint_every: Option<usize>,
+}
+
+impl CheckpointArgs {
+    /// Pass each of `generations` rows to `f`, stepping between them and saving
+    /// checkpoints as configured. A checkpoint holds the state of the next generation
+    /// to output, so a resumed run carries on without repeating a row.
+    fn for_each_row(
+        &self,
+        automaton: &mut Automaton,
+        generations: usize,
+        mut f: impl FnMut(&Automaton),
+    ) {
+        for gen in 0..generations {
+            f(automaton);
+            if gen + 1 < generations || self.checkpoint.is_some() {
+                self.step(automaton);
+            }
+        }
+        if let Some(path) = &self.checkpoint {
+            save_checkpoint(automaton, path);
+        }
+    }
+
+    /// Step once, saving a checkpoint if one is due.
+    fn step(&self, automaton: &mut Automaton) {
+        automaton.step();
+        if let (Some(path), Some(every)) = (&self.checkpoint, self.checkpoint_every) {
+            if every > 0 && automaton.generation().is_multiple_of(every) {
+                save_checkpoint(automaton, path);
+            }
+        }
+    }
+}
+
+/// Write a checkpoint via a temporary file, so an interrupted save never leaves a broken one.
+fn save_checkpoint(automaton: &Automaton, path: &str) {
+    let tmp = format!("{}.tmp", path);
+    std::fs::write(&tmp, automaton.to_checkpoint()).expect("Failed to write checkpoint");
+    std::fs::rename(&tmp, path).expect("Failed to write checkpoint");
+}
 
+/// Options controlling how generations are printed to the terminal.
+#[derive(clap::Args, Debug)]
+struct TerminalArgs {
+    /// Terminal renderer: block (one cell per character), half (two generations per
+    /// line) or braille (2x4 cells per character)
+    #[arg(long, default_value = "block")]
+    terminal: String,
+
+    /// Print in 24-bit ANSI colour following the dead/alive color gradients
+    #[arg(long, default_value_t = false)]
+    color: bool,
+
+    /// Set the width to fill the terminal (not with --init or --resume, which fix the width)
+    #[arg(long, default_value_t = false, conflicts_with_all = ["init", "resume"])]
+    fit: bool,
+
+    /// Keep running and scroll new generations until interrupted
+    #[arg(long, default_value_t = false)]
+    live: bool,
+
+    /// Delay between lines in live mode, in milliseconds
+    #[arg(long, default_value_t = 50)]
+    delay: u64,
+}
+
+/// Options controlling how generations are drawn to PNG.
+#[derive(clap::Args, Debug)]
+struct RenderArgs {
     /// Shape to use for alive cells in PNG output
     #[arg(long, default_value = "square")]
     alive_shape: String,
@@ -47,10 +165,6 @@ struct Args {
     #[arg(long, short = 's', default_value_t = 1)]
     scale: usize,
 
-    /// Output PNG file
-    #[arg(long, short = 'o')]
-    output: Option<String>,
-
     /// Start color for dead cells
     #[arg(long, default_value = "#ffaaff")]
     dead_color_from: String,
@@ -66,68 +180,1015 @@ struct Args {
     /// End color for alive cells
     #[arg(long, default_value = "#aaffff")]
     alive_color_to: String,
+
+    /// Stack the rule icon (the eight neighbourhoods and their outputs) above the diagram
+    #[arg(long, default_value_t = false)]
+    rule_icon: bool,
+
+    /// Save an animation adding this many generations per frame (APNG for .png output;
+    /// .gif output is always a GIF, with one frame unless this is given)
+    #[arg(long, conflicts_with = "rule_icon")]
+    animate: Option<usize>,
+
+    /// Milliseconds between animation frames
+    #[arg(long, default_value_t = 50)]
+    frame_delay: u16,
 }
 
-fn parse_hex_color(s: &str) -> Rgb<u8> {
-    let s = s.strip_prefix('#').unwrap_or(s);
-    assert!(s.len() == 6, "Color must be in format #RRGGBB");
-    let r = u8::from_str_radix(&s[0..2], 16).expect("Invalid hex color");
-    let g = u8::from_str_radix(&s[2..4], 16).expect("Invalid hex color");
-    let b = u8::from_str_radix(&s[4..6], 16).expect("Invalid hex color");
-    Rgb([r, g, b])
+impl RenderArgs {
+    /// Parsed dead from/to and alive from/to colors.
+    fn colors(&self) -> (Rgb<u8>, Rgb<u8>, Rgb<u8>, Rgb<u8>) {
+        (
+            parse_hex_color(&self.dead_color_from),
+            parse_hex_color(&self.dead_color_to),
+            parse_hex_color(&self.alive_color_from),
+            parse_hex_color(&self.alive_color_to),
+        )
+    }
+
+    /// Render generations and save them as a PNG, or as an SVG or GIF if `path` ends in
+    /// `.svg` or `.gif`. With `--animate` a PNG is animated (APNG).
+    ///
+    /// `overlay` blends a second color over the cells set in a mask (used for damage),
+    /// and with `--rule-icon` the icon of `rule` is stacked above the diagram. The image
+    /// embeds `permalink_code` in its metadata when given.
+    fn save(
+        &self,
+        generations_vec: &[Vec<u8>],
+        width: usize,
+        height: usize,
+        path: &str,
+        rule: u8,
+        overlay: Option<(&[Vec<u8>], Rgb<u8>)>,
+        permalink_code: Option<&str>,
+    ) {
+        let (dead_from, dead_to, alive_from, alive_to) = self.colors();
+        let scale = self.scale;
+        let (diagram_width, diagram_height) = (width * scale, height * scale);
+        let icon_width = image_output::RULE_ICON_WIDTH * scale;
+        let icon_height = image_output::RULE_ICON_HEIGHT * scale;
+
+        let metadata = permalink_code.map(|code| (permalink::PNG_KEYWORD, code));
+
+        if path.ends_with(".svg") {
+            assert!(self.animate.is_none(), "SVG output cannot be animated");
+            let (img_width, x_offset, y_offset) = if self.rule_icon {
+                (diagram_width.max(icon_width), 0, icon_height + scale)
+            } else {
+                (diagram_width, 0, 0)
+            };
+            let x_offset = x_offset + (img_width - diagram_width) / 2;
+            let mut elements = String::new();
+            if self.rule_icon {
+                elements.push_str(&image_output::rule_icon_to_svg_elements(
+                    rule,
+                    scale,
+                    &self.alive_shape,
+                    &self.dead_shape,
+                    dead_from,
+                    dead_to,
+                    alive_from,
+                    alive_to,
+                    (img_width - icon_width) / 2,
+                    0,
+                ));
+            }
+            elements.push_str(&image_output::generations_to_svg_elements(
+                generations_vec,
+                width,
+                height,
+                scale,
+                &self.alive_shape,
+                &self.dead_shape,
+                self.links,
+                dead_from,
+                dead_to,
+                alive_from,
+                alive_to,
+                x_offset,
+                y_offset,
+            ));
+            if let Some((mask, color)) = overlay {
+                elements.push_str(&image_output::overlay_cells_svg_elements(
+                    mask, scale, color, x_offset, y_offset,
+                ));
+            }
+            let svg = image_output::svg_document(
+                &elements,
+                img_width,
+                y_offset + diagram_height,
+                metadata,
+            );
+            std::fs::write(path, svg).expect("Failed to save SVG");
+            return;
+        }
+
+        let gif = path.ends_with(".gif");
+        if gif || self.animate.is_some() {
+            assert!(
+                overlay.is_none() && !self.rule_icon,
+                "Animations cannot show damage or the rule icon"
+            );
+            let animation = image_output::generations_to_animation(
+                generations_vec,
+                width,
+                height,
+                scale,
+                &self.alive_shape,
+                &self.dead_shape,
+                self.links,
+                dead_from,
+                dead_to,
+                alive_from,
+                alive_to,
+                self.animate.unwrap_or(height),
+                self.frame_delay,
+                if gif {
+                    image_output::AnimationFormat::Gif
+                } else {
+                    image_output::AnimationFormat::Apng
+                },
+                metadata,
+            );
+            std::fs::write(path, animation).expect("Failed to save animation");
+            return;
+        }
+
+        let mut buffer = image_output::generations_to_rgba_buffer(
+            generations_vec,
+            width,
+            height,
+            scale,
+            &self.alive_shape,
+            &self.dead_shape,
+            self.links,
+            dead_from,
+            dead_to,
+            alive_from,
+            alive_to,
+        );
+        if let Some((mask, color)) = overlay {
+            image_output::overlay_cells_rgba(&mut buffer, mask, width, scale, color);
+        }
+        let (mut img_width, mut img_height) = (diagram_width, diagram_height);
+        if self.rule_icon {
+            let icon = image_output::rule_icon_to_rgba_buffer(
+                rule,
+                scale,
+                &self.alive_shape,
+                &self.dead_shape,
+                dead_from,
+                dead_to,
+                alive_from,
+                alive_to,
+            );
+            (buffer, img_width, img_height) =
+                image_output::stack_rgba_buffers(&icon, icon_width, &buffer, diagram_width, scale);
+        }
+        match permalink_code {
+            Some(code) => image_output::save_rgba_buffer_as_png_with_text(
+                buffer,
+                img_width as u32,
+                img_height as u32,
+                path,
+                permalink::PNG_KEYWORD,
+                code,
+            ),
+            None => image_output::save_rgba_buffer_as_png(
+                buffer,
+                img_width as u32,
+                img_height as u32,
+                path,
+            ),
+        }
+    }
+
+    /// Save only the rule icon, as a PNG or SVG.
+    fn save_icon(&self, rule: u8, path: &str) {
+        let (dead_from, dead_to, alive_from, alive_to) = self.colors();
+        let scale = self.scale;
+        let icon_width = image_output::RULE_ICON_WIDTH * scale;
+        let icon_height = image_output::RULE_ICON_HEIGHT * scale;
+        if path.ends_with(".svg") {
+            let elements = image_output::rule_icon_to_svg_elements(
+                rule,
+                scale,
+                &self.alive_shape,
+                &self.dead_shape,
+                dead_from,
+                dead_to,
+                alive_from,
+                alive_to,
+                0,
+                0,
+            );
+            image_output::save_svg(&elements, icon_width, icon_height, path);
+        } else {
+            let buffer = image_output::rule_icon_to_rgba_buffer(
+                rule,
+                scale,
+                &self.alive_shape,
+                &self.dead_shape,
+                dead_from,
+                dead_to,
+                alive_from,
+                alive_to,
+            );
+            image_output::save_rgba_buffer_as_png(
+                buffer,
+                icon_width as u32,
+                icon_height as u32,
+                path,
+            );
+        }
+    }
 }
 
-fn main() {
-    let args = Args::parse();
-    let random_distribution = match args.random_distribution.as_str() {
-        "none" => None,
-        s => Some(s.parse().expect("Invalid random_distribution")),
+/// Options shared by every mode that runs the automaton.
+#[derive(clap::Args, Debug)]
+struct SimArgs {
+    /// Rule number (0-255), transition string ("111:0 110:1 ...") or Golly .table file
+    #[arg(value_parser = parse_rule_arg, required_unless_present_any = ["resume", "from_url"])]
+    rule: Option<u8>,
+
+    /// Continue a run from a checkpoint saved with --checkpoint (replaces the rule and
+    /// all other simulation options, which cannot be given with it)
+    #[arg(
+        long,
+        value_parser = parse_checkpoint_arg,
+        conflicts_with_all = [
+            "rule",
+            "seed",
+            "seed_scheme",
+            "random_distribution",
+            "width",
+            "init",
+            "boundary",
+            "family",
+            "probabilities",
+            "noise",
+            "update",
+            "alpha",
+        ]
+    )]
+    resume: Option<Automaton>,
+
+    /// Take the rule, simulation and image options from a web UI permalink (the URL or
+    /// its code) or a PNG saved with one (replaces those options)
+    #[arg(long, value_parser = parse_permalink_arg, conflicts_with_all = ["resume", "init"])]
+    from_url: Option<Config>,
+
+    /// Random seed (u64, optional)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// How the seed is expanded into the RNG: 2 (PCG64, the default), 1 (Xoshiro256++, for
+    /// CLI seeds published before scheme 2) or 3 (Xoshiro128++, for earlier web UI seeds)
+    #[arg(long, default_value = "2")]
+    seed_scheme: String,
+
+    /// Probability for random initial state (0.0-1.0), or 'none' for single center cell
+    #[arg(long, short = 'd', default_value = "none")]
+    random_distribution: String,
+
+    /// Width of the automaton
+    #[arg(long, short = 'w', default_value_t = 64)]
+    width: usize,
+
+    /// Number of generations to run
+    #[arg(long, short = 'g', default_value_t = 32)]
+    generations: usize,
+
+    /// Start from the last row of a previous run, read from a file or '-' for stdin in any
+    /// --format (replaces --random-distribution and --width)
+    #[arg(long, value_parser = parse_history_arg)]
+    init: Option<History>,
+
+    /// Boundary condition: dead (cells beyond the edges are dead) or periodic (wrap around)
+    #[arg(long, default_value = "dead")]
+    boundary: String,
+
+    /// Rule family: elementary or second-order (reversible Fredkin construction)
+    #[arg(long, default_value = "elementary")]
+    family: String,
+
+    /// Probabilistic rule: chance that each neighbourhood 000..111 produces an alive cell
+    /// (8 comma separated values, replaces the rule)
+    #[arg(long, value_delimiter = ',')]
+    probabilities: Option<Vec<f64>>,
+
+    /// Probability of flipping each cell's output every generation
+    #[arg(long, default_value_t = 0.0)]
+    noise: f64,
+
+    /// Update mode: sync, alpha (each cell updates with probability --alpha) or sequential
+    #[arg(long, default_value = "sync")]
+    update: String,
+
+    /// Probability that a cell is updated each generation in alpha mode
+    #[arg(long, default_value_t = 1.0)]
+    alpha: f64,
+}
+
+impl SimArgs {
+    /// Rule number, taken from `--resume` if given.
+    fn rule(&self) -> u8 {
+        match &self.resume {
+            Some(automaton) => automaton.rule(),
+            None => self.rule.expect("Rule is required"),
+        }
+    }
+
+    /// Width of the automaton, taken from `--resume` or `--init` if given.
+    fn width(&self) -> usize {
+        match (&self.resume, &self.init) {
+            (Some(automaton), _) => automaton.row().len(),
+            (None, Some(History(rows))) => rows[0].len(),
+            (None, None) => self.width,
+        }
+    }
+
+    /// Permalink code reproducing this run drawn with `render`, if there is one. Runs with
+    /// stochastic options, a checkpoint or a random initial row but no seed have none.
+    fn permalink(&self, render: &RenderArgs) -> Option<String> {
+        let family = RuleFamily::parse(&self.family);
+        let random_distribution = self.random_distribution();
+        let unseeded_random = self.init.is_none()
+            && self.seed.is_none()
+            && random_distribution.is_some_and(|p| p > 0.0);
+        if self.resume.is_some()
+            || unseeded_random
+            || self.probabilities.is_some()
+            || self.noise > 0.0
+            || self.update != "sync"
+        {
+            return None;
+        }
+        let initial_row = match &self.init {
+            // A second-order run also depends on the row before the last
+            Some(History(rows)) if rows.len() > 1 && family == RuleFamily::SecondOrder => {
+                return None
+            }
+            Some(History(rows)) => Some(rows[rows.len() - 1].clone()),
+            None => None,
+        };
+        let (dead_from, dead_to, alive_from, alive_to) = render.colors();
+        Config {
+            rule: self.rule(),
+            random_distribution,
+            width: self.width(),
+            generations: self.generations,
+            seed: self.seed,
+            seed_scheme: SeedScheme::parse(&self.seed_scheme),
+            family,
+            boundary: Boundary::parse(&self.boundary),
+            initial_row,
+            scale: render.scale,
+            alive_shape: render.alive_shape.clone(),
+            dead_shape: render.dead_shape.clone(),
+            links: render.links,
+            dead_from,
+            dead_to,
+            alive_from,
+            alive_to,
+        }
+        .encode()
+        .ok()
+    }
+
+    /// Build the automaton described by these options.
+    fn automaton(&self) -> Automaton {
+        if let Some(automaton) = &self.resume {
+            return automaton.clone();
+        }
+        let rule = self.rule();
+        let scheme = SeedScheme::parse(&self.seed_scheme);
+        let automaton = match &self.init {
+            Some(History(rows)) => {
+                let automaton = Automaton::from_row(rule, rows[rows.len() - 1].clone())
+                    .with_seed_scheme(self.seed, scheme);
+                match rows.len() {
+                    1 => automaton,
+                    n => automaton.with_previous(rows[n - 2].clone()),
+                }
+            }
+            None => Automaton::new_with_scheme(
+                rule,
+                self.random_distribution(),
+                self.width,
+                self.seed,
+                scheme,
+            ),
+        };
+        let automaton = automaton
+            .with_boundary(Boundary::parse(&self.boundary))
+            .with_family(RuleFamily::parse(&self.family))
+            .with_noise(self.noise)
+            .with_update_mode(UpdateMode::parse(&self.update, self.alpha));
+        match &self.probabilities {
+            Some(p) => {
+                let probabilities: [f64; 8] = p
+                    .as_slice()
+                    .try_into()
+                    .expect("Probabilities must have 8 values");
+                automaton.with_probabilities(probabilities)
+            }
+            None => automaton,
+        }
+    }
+
+    fn random_distribution(&self) -> Option<f64> {
+        match self.random_distribution.as_str() {
+            "none" => None,
+            s => Some(s.parse().expect("Invalid random_distribution")),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Report per-generation statistics without rendering any images
+    Stats(StatsArgs),
+    /// Estimate the Wolfram class of all 256 elementary rules
+    Classify(ClassifyArgs),
+    /// Print mirror, complement and canonical equivalents of every elementary rule
+    Symmetry(SymmetryArgs),
+    /// Run a second-order automaton forward then backward and check the initial row is recovered
+    ReverseCheck(ReverseCheckArgs),
+    /// Find rows that evolve into a target row, or reconstruct its history
+    Preimage(PreimageArgs),
+    /// Print a rule as both a rule number and a transition table
+    Table(TableArgs),
+    /// Draw the rule icon: each neighbourhood with its output cell underneath
+    Icon(IconArgs),
+    /// Render generations from a file or stdin without simulating
+    Render(RenderCommandArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct StatsArgs {
+    #[command(flatten)]
+    sim: SimArgs,
+
+    /// Output format: csv or json
+    #[arg(long, short = 'f', default_value = "csv")]
+    format: String,
+
+    /// Block sizes for Shannon block entropy (comma separated)
+    #[arg(long, value_delimiter = ',', default_value = "1,2,3")]
+    block_sizes: Vec<usize>,
+
+    /// Lags for spatial autocorrelation (comma separated)
+    #[arg(long, value_delimiter = ',', default_value = "1")]
+    lags: Vec<usize>,
+
+    /// Output file (defaults to stdout)
+    #[arg(long, short = 'o')]
+    output: Option<String>,
+
+    /// Also draw the statistics as a line chart PNG
+    #[arg(long)]
+    chart: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ClassifyArgs {
+    /// Width of the ring used for each trial
+    #[arg(long, short = 'w', default_value_t = 201)]
+    width: usize,
+
+    /// Number of generations per trial
+    #[arg(long, short = 'g', default_value_t = 1024)]
+    generations: usize,
+
+    /// Number of random initial conditions per rule
+    #[arg(long, short = 't', default_value_t = 4)]
+    trials: usize,
+
+    /// Seed for the random initial conditions
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Only classify the canonical rule of each equivalence class
+    #[arg(long, default_value_t = false)]
+    unique: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReverseCheckArgs {
+    #[command(flatten)]
+    sim: SimArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct PreimageArgs {
+    /// Rule number (0-255), transition string ("111:0 110:1 ...") or Golly .table file
+    #[arg(value_parser = parse_rule_arg)]
+    rule: u8,
+
+    /// Target row as a string of 0s and 1s
+    #[arg(long, short = 't')]
+    target: String,
+
+    /// Boundary condition: dead or periodic
+    #[arg(long, default_value = "dead")]
+    boundary: String,
+
+    /// Maximum number of preimages to print
+    #[arg(long, default_value_t = 16)]
+    limit: usize,
+
+    /// Reconstruct a history of this many generations ending in the target
+    #[arg(long, short = 'g', default_value_t = 1)]
+    generations: usize,
+
+    /// Maximum number of preimages tried per generation while backtracking
+    #[arg(long, default_value_t = 64)]
+    branching: usize,
+
+    #[command(flatten)]
+    render: RenderArgs,
+
+    /// Output PNG file for the reconstructed history
+    #[arg(long, short = 'o')]
+    output: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct TableArgs {
+    /// Rule number (0-255), transition string ("111:0 110:1 ...") or Golly .table file
+    #[arg(value_parser = parse_rule_arg)]
+    rule: u8,
+}
+
+#[derive(clap::Args, Debug)]
+struct IconArgs {
+    /// Rule number (0-255), transition string ("111:0 110:1 ...") or Golly .table file
+    #[arg(value_parser = parse_rule_arg)]
+    rule: u8,
+
+    #[command(flatten)]
+    render: RenderArgs,
+
+    /// Output PNG or SVG file
+    #[arg(long, short = 'o')]
+    output: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct RenderCommandArgs {
+    /// Generations to render, from a file or '-' for stdin, in any --format
+    #[arg(value_parser = parse_history_arg)]
+    input: History,
+
+    /// Rule drawn by --rule-icon
+    #[arg(long, value_parser = parse_rule_arg)]
+    rule: Option<u8>,
+
+    #[command(flatten)]
+    render: RenderArgs,
+
+    /// Output PNG or SVG file
+    #[arg(long, short = 'o')]
+    output: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct SymmetryArgs {
+    /// Print one line per equivalence class instead of one per rule
+    #[arg(long, default_value_t = false)]
+    classes: bool,
+
+    /// Only print the canonical rule numbers, one per line (for scripts)
+    #[arg(long, default_value_t = false)]
+    canonical_only: bool,
+}
+
+/// Open the output file, or stdout when no path is given.
+fn open_output(path: Option<&str>) -> BufWriter<Box<dyn Write>> {
+    let out: Box<dyn Write> = match path {
+        Some(path) => Box::new(File::create(path).expect("Failed to create output file")),
+        None => Box::new(io::stdout().lock()),
     };
-    let seed = args.seed;
-    let flat_vec = run_automaton(
+    BufWriter::new(out)
+}
+
+fn run_stats(args: &StatsArgs) {
+    let sim = &args.sim;
+    let json = match args.format.as_str() {
+        "csv" => false,
+        "json" => true,
+        other => panic!("Unknown stats format: {}", other),
+    };
+    let mut automaton = sim.automaton();
+    let mut collector = StatsCollector::new(&args.block_sizes, &args.lags);
+    let mut out = open_output(args.output.as_deref());
+    let mut records = Vec::new();
+
+    if json {
+        writeln!(out, "[").expect("Failed to write stats");
+    } else {
+        writeln!(out, "{}", collector.csv_header()).expect("Failed to write stats");
+    }
+    for gen in 0..sim.generations {
+        if gen > 0 {
+            automaton.step();
+        }
+        let stats = collector.push_row(automaton.row());
+        if json {
+            let sep = if gen + 1 < sim.generations { "," } else { "" };
+            writeln!(out, "  {}{}", stats.to_json(), sep).expect("Failed to write stats");
+        } else {
+            writeln!(out, "{}", stats.to_csv()).expect("Failed to write stats");
+        }
+        if args.chart.is_some() {
+            records.push(stats);
+        }
+    }
+    if json {
+        writeln!(out, "]").expect("Failed to write stats");
+    }
+    out.flush().expect("Failed to write stats");
+
+    if let Some(chart_path) = &args.chart {
+        save_chart(&records, chart_path, 800, 400);
+    }
+}
+
+fn run_classify(args: &ClassifyArgs) {
+    println!("rule,class,compressibility,entropy_growth,damage,period");
+    for rule in 0..=255u8 {
+        if args.unique && !symmetry::is_canonical(rule) {
+            continue;
+        }
+        let (class, metrics) =
+            estimate_class(rule, args.width, args.generations, args.trials, args.seed)
+                .unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                });
+        println!(
+            "{},{},{:.3},{:.3},{:.3},{}",
+            rule,
+            class as u8,
+            metrics.compressibility,
+            metrics.entropy_growth,
+            metrics.damage,
+            metrics.period.map_or(String::new(), |p| p.to_string())
+        );
+    }
+}
+
+fn run_table(args: &TableArgs) {
+    println!("rule {}", args.rule);
+    println!("{}", rule_table::RuleTable::from_rule(args.rule));
+}
+
+fn run_symmetry(args: &SymmetryArgs) {
+    if args.canonical_only {
+        for class in symmetry::equivalence_classes() {
+            println!("{}", class[0]);
+        }
+    } else if args.classes {
+        println!("canonical,rules");
+        for class in symmetry::equivalence_classes() {
+            let rules: Vec<String> = class.iter().map(|r| r.to_string()).collect();
+            println!("{},{}", class[0], rules.join(" "));
+        }
+    } else {
+        println!("rule,mirror,complement,mirror_complement,canonical");
+        for rule in 0..=255u8 {
+            println!(
+                "{},{},{},{},{}",
+                rule,
+                symmetry::mirror(rule),
+                symmetry::complement(rule),
+                symmetry::mirror_complement(rule),
+                symmetry::canonical(rule)
+            );
+        }
+    }
+}
+
+fn run_reverse_check(args: &ReverseCheckArgs) {
+    let sim = &args.sim;
+    let mut automaton = sim.automaton().with_family(RuleFamily::SecondOrder);
+    let initial = automaton.row().to_vec();
+    for _ in 1..sim.generations {
+        automaton.step();
+    }
+    for _ in 1..sim.generations {
+        automaton.step_back();
+    }
+    if automaton.row() == initial.as_slice() {
+        println!(
+            "OK: recovered the initial row after {} generations forward and back",
+            sim.generations.saturating_sub(1)
+        );
+    } else {
+        eprintln!("FAILED: the initial row was not recovered");
+        std::process::exit(1);
+    }
+}
+
+/// Generations read from a file or stdin by `--init` or `render`.
+#[derive(Debug, Clone)]
+struct History(Vec<Vec<u8>>);
+
+/// Read generations from a path, or stdin for '-', in any format `--format` writes.
+fn parse_history_arg(s: &str) -> Result<History, String> {
+    let mut data = Vec::new();
+    if s == "-" {
+        io::stdin()
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    } else {
+        data = std::fs::read(s).map_err(|e| format!("Failed to read {}: {}", s, e))?;
+    }
+    read_generations(&data).map(History)
+}
+
+/// Load an automaton from a checkpoint file.
+fn parse_checkpoint_arg(s: &str) -> Result<Automaton, String> {
+    let data = std::fs::read(s).map_err(|e| format!("Failed to read {}: {}", s, e))?;
+    Automaton::from_checkpoint(&data)
+}
+
+/// Parse a permalink, reading it from a PNG first if the argument names a file.
+fn parse_permalink_arg(s: &str) -> Result<Config, String> {
+    if std::path::Path::new(s).is_file() {
+        let data = std::fs::read(s).map_err(|e| format!("Failed to read {}: {}", s, e))?;
+        return Config::from_png(&data);
+    }
+    Config::decode(s)
+}
+
+/// Replace the simulation and image options with those of a permalink.
+fn apply_permalink(config: &Config, sim: &mut SimArgs, render: &mut RenderArgs) {
+    sim.rule = Some(config.rule);
+    sim.random_distribution = match config.random_distribution {
+        Some(p) => p.to_string(),
+        None => "none".to_string(),
+    };
+    sim.width = config.width;
+    sim.generations = config.generations;
+    sim.seed = config.seed;
+    sim.seed_scheme = config.seed_scheme.version().to_string();
+    sim.family = config.family.name().to_string();
+    sim.boundary = config.boundary.name().to_string();
+    sim.init = config.initial_row.clone().map(|row| History(vec![row]));
+    render.scale = config.scale;
+    render.alive_shape = config.alive_shape.clone();
+    render.dead_shape = config.dead_shape.clone();
+    render.links = config.links;
+    render.dead_color_from = format_hex_color(config.dead_from);
+    render.dead_color_to = format_hex_color(config.dead_to);
+    render.alive_color_from = format_hex_color(config.alive_from);
+    render.alive_color_to = format_hex_color(config.alive_to);
+}
+
+/// Parse a rule argument, reading it from a file first if it names one.
+fn parse_rule_arg(s: &str) -> Result<u8, String> {
+    if std::path::Path::new(s).is_file() {
+        let contents =
+            std::fs::read_to_string(s).map_err(|e| format!("Failed to read {}: {}", s, e))?;
+        return rule_table::parse_rule(&contents);
+    }
+    rule_table::parse_rule(s)
+}
+
+/// Parse a row written as a string of 0s and 1s.
+fn parse_row(s: &str) -> Vec<u8> {
+    s.trim()
+        .chars()
+        .map(|c| match c {
+            '0' => 0,
+            '1' => 1,
+            _ => panic!("Row must only contain 0 and 1, found '{}'", c),
+        })
+        .collect()
+}
+
+fn format_row(row: &[u8]) -> String {
+    row.iter()
+        .map(|&c| if c == 1 { '1' } else { '0' })
+        .collect()
+}
+
+fn run_preimage(args: &PreimageArgs) {
+    let target = parse_row(&args.target);
+    let boundary = Boundary::parse(&args.boundary);
+    if args.generations <= 1 {
+        let count = count_preimages(args.rule, &target, boundary);
+        if count == 0 {
+            println!("Garden of Eden: no row evolves into the target");
+            return;
+        }
+        println!("{} preimage(s)", count);
+        for preimage in find_preimages(args.rule, &target, boundary, args.limit) {
+            println!("{}", format_row(&preimage));
+        }
+        return;
+    }
+
+    let Some(history) = backtrack(
         args.rule,
-        random_distribution,
-        args.width,
+        &target,
+        boundary,
         args.generations,
-        seed,
+        args.branching,
+    ) else {
+        println!(
+            "No history of {} generations found (every branch reached a Garden of Eden)",
+            args.generations
+        );
+        return;
+    };
+    match &args.output {
+        Some(path) => args.render.save(
+            &history,
+            target.len(),
+            history.len(),
+            path,
+            args.rule,
+            None,
+            None,
+        ),
+        None => {
+            for row in &history {
+                println!("{}", format_row(row));
+            }
+        }
+    }
+}
+
+fn run_render(args: &RenderCommandArgs) {
+    let History(rows) = &args.input;
+    if args.render.rule_icon && args.rule.is_none() {
+        panic!("--rule-icon needs --rule");
+    }
+    args.render.save(
+        rows,
+        rows[0].len(),
+        rows.len(),
+        &args.output,
+        args.rule.unwrap_or(0),
+        None,
+        None,
+    );
+}
+
+/// Stream the generations to `path` (or stdout) in a data format.
+fn write_data(sim: &SimArgs, format: DataFormat, path: Option<&str>, checkpoint: &CheckpointArgs) {
+    let mut writer = data_writer(sim, format, path);
+    checkpoint.for_each_row(&mut sim.automaton(), sim.generations, |automaton| {
+        writer
+            .write_row(automaton.row())
+            .expect("Failed to write output");
+    });
+    writer.finish().expect("Failed to write output");
+}
+
+/// Write generations that were already simulated, without running the automaton again.
+fn write_rows(sim: &SimArgs, format: DataFormat, path: Option<&str>, rows: &[Vec<u8>]) {
+    let mut writer = data_writer(sim, format, path);
+    for row in rows {
+        writer.write_row(row).expect("Failed to write output");
+    }
+    writer.finish().expect("Failed to write output");
+}
+
+fn data_writer(
+    sim: &SimArgs,
+    format: DataFormat,
+    path: Option<&str>,
+) -> DataWriter<BufWriter<Box<dyn Write>>> {
+    DataWriter::new(
+        open_output(path),
+        format,
+        sim.rule(),
+        sim.width(),
+        sim.generations,
+    )
+    .expect("Failed to write output")
+}
+
+/// Step the automaton forever, printing each line as soon as its generations are ready.
+fn run_live(sim: &SimArgs, renderer: &TerminalRenderer, delay: u64, checkpoint: &CheckpointArgs) {
+    let mut automaton = sim.automaton();
+    let mut rows: Vec<Vec<u8>> = Vec::with_capacity(renderer.rows_per_line());
+    let mut stdout = io::stdout().lock();
+    loop {
+        rows.push(automaton.row().to_vec());
+        if rows.len() == renderer.rows_per_line() {
+            let line_rows: Vec<&[u8]> = rows.iter().map(|row| row.as_slice()).collect();
+            let first = automaton.generation() + 1 - rows.len();
+            // Stop quietly once stdout is closed (e.g. piped into head)
+            if writeln!(stdout, "{}", renderer.render_line(&line_rows, first)).is_err()
+                || stdout.flush().is_err()
+            {
+                return;
+            }
+            rows.clear();
+            std::thread::sleep(std::time::Duration::from_millis(delay));
+        }
+        checkpoint.step(&mut automaton);
+    }
+}
+
+fn main() {
+    let mut args = Args::parse();
+    if let Some(command) = &args.command {
+        match command {
+            Command::Stats(stats_args) => run_stats(stats_args),
+            Command::Classify(classify_args) => run_classify(classify_args),
+            Command::Symmetry(symmetry_args) => run_symmetry(symmetry_args),
+            Command::ReverseCheck(check_args) => run_reverse_check(check_args),
+            Command::Preimage(preimage_args) => run_preimage(preimage_args),
+            Command::Table(table_args) => run_table(table_args),
+            Command::Render(render_args) => run_render(render_args),
+            Command::Icon(icon_args) => icon_args
+                .render
+                .save_icon(icon_args.rule, &icon_args.output),
+        }
+        return;
+    }
+    let mut sim = args.sim.expect("Rule is required");
+    if let Some(config) = sim.from_url.take() {
+        apply_permalink(&config, &mut sim, &mut args.render);
+    }
+    let mode = TerminalMode::parse(&args.terminal.terminal);
+    if args.terminal.fit {
+        sim.width = terminal_columns() * mode.cells_per_char();
+    }
+    let renderer = TerminalRenderer::new(
+        mode,
+        args.terminal.color.then(|| args.render.colors()),
+        sim.width(),
+        sim.generations,
     );
+    if args.terminal.live {
+        run_live(&sim, &renderer, args.terminal.delay, &args.checkpoint);
+        return;
+    }
+    if let Some(format) = &args.format {
+        write_data(
+            &sim,
+            DataFormat::parse(format),
+            args.output.as_deref(),
+            &args.checkpoint,
+        );
+        return;
+    }
+    let start = sim.automaton();
+    let mut flat_vec = Vec::with_capacity(sim.generations * sim.width());
+    args.checkpoint
+        .for_each_row(&mut start.clone(), sim.generations, |automaton| {
+            flat_vec.extend_from_slice(automaton.row())
+        });
     let generations_vec: Vec<Vec<u8>> = flat_vec
-        .chunks(args.width)
+        .chunks(sim.width())
         .map(|chunk| chunk.to_vec())
         .collect();
+    let damage_vec: Option<Vec<Vec<u8>>> = if args.damage {
+        let flip_index = args.damage_cell.unwrap_or(sim.width() / 2);
+        let diff = damage_spread(&start, sim.generations, flip_index);
+        eprintln!(
+            "Damage growth rate: {:.4} cells/generation",
+            damage_growth_rate(&diff, sim.width())
+        );
+        Some(
+            diff.chunks(sim.width())
+                .map(|chunk| chunk.to_vec())
+                .collect(),
+        )
+    } else {
+        None
+    };
 
     if let Some(output_path) = args.output {
-        let dead_from = parse_hex_color(&args.dead_color_from);
-        let dead_to = parse_hex_color(&args.dead_color_to);
-        let alive_from = parse_hex_color(&args.alive_color_from);
-        let alive_to = parse_hex_color(&args.alive_color_to);
-        image_output::save_generations_as_png(
+        let damage_color = parse_hex_color(&args.damage_color);
+        // The permalink only describes the plain diagram
+        let permalink = if damage_vec.is_none() && !args.render.rule_icon {
+            sim.permalink(&args.render)
+        } else {
+            None
+        };
+        args.render.save(
             &generations_vec,
-            args.width,
-            args.generations,
-            args.scale,
-            &args.alive_shape,
-            &args.dead_shape,
-            args.links,
+            sim.width(),
+            sim.generations,
             &output_path,
-            dead_from,
-            dead_to,
-            alive_from,
-            alive_to,
+            sim.rule(),
+            damage_vec.as_deref().map(|mask| (mask, damage_color)),
+            permalink.as_deref(),
         );
     } else if args.pretty_print {
-        for gen in generations_vec {
-            for cell in gen {
-                print!("{}", if cell == 1 { '█' } else { ' ' });
-            }
-            println!();
-        }
+        let mut out = BufWriter::new(io::stdout().lock());
+        renderer
+            .write_all(&mut out, &generations_vec)
+            .expect("Failed to write to stdout");
     } else {
-        for gen in generations_vec {
-            for cell in gen {
-                print!("{}", cell);
-            }
-            println!();
-        }
+        write_rows(&sim, DataFormat::Digits, None, &generations_vec);
     }
 }
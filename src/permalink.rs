@@ -0,0 +1,312 @@
+use crate::{Boundary, RuleFamily, SeedScheme};
+use image::Rgb;
+
+// Permalink layout, base64url encoded without padding:
+//
+//   version u8
+//   rule u8, flags u8 (LINKS, DISTRIBUTION, SEED, SECOND_ORDER, PERIODIC, INITIAL_ROW)
+//   width, generations, scale as LEB128 varints
+//   random distribution f64 (little-endian) if DISTRIBUTION
+//   seed as a varint if SEED, then seed scheme u8
+//   alive shape u8, dead shape u8 (indices into SHAPES)
+//   dead from, dead to, alive from, alive to as RGB bytes
+//   initial row packed 8 cells per byte (first cell in the high bit) if INITIAL_ROW
+
+const VERSION: u8 = 1;
+
+/// Keyword of the PNG `tEXt` chunk that holds the permalink of a saved image.
+pub const PNG_KEYWORD: &str = "permalink";
+
+const LINKS: u8 = 1;
+const DISTRIBUTION: u8 = 2;
+const SEED: u8 = 4;
+const SECOND_ORDER: u8 = 8;
+const PERIODIC: u8 = 16;
+const INITIAL_ROW: u8 = 32;
+
+/// Cell shape names in the order of their permalink indices. Only ever append.
+const SHAPES: [&str; 11] = [
+    "square",
+    "circle",
+    "circle-small",
+    "triangle-up",
+    "triangle-down",
+    "triangle-left",
+    "triangle-right",
+    "triangle-r-a",
+    "triangle-r-b",
+    "triangle-r-c",
+    "triangle-r-d",
+];
+
+/// Everything needed to reproduce an image: the run and how it is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub rule: u8,
+    /// Probability of alive cells in a random initial row, or `None` for a centre cell.
+    pub random_distribution: Option<f64>,
+    pub width: usize,
+    pub generations: usize,
+    pub seed: Option<u64>,
+    pub seed_scheme: SeedScheme,
+    pub family: RuleFamily,
+    pub boundary: Boundary,
+    /// Explicit initial row, replacing the random distribution.
+    pub initial_row: Option<Vec<u8>>,
+    pub scale: usize,
+    /// Shape names as accepted by the CLI and web UI.
+    pub alive_shape: String,
+    pub dead_shape: String,
+    pub links: bool,
+    pub dead_from: Rgb<u8>,
+    pub dead_to: Rgb<u8>,
+    pub alive_from: Rgb<u8>,
+    pub alive_to: Rgb<u8>,
+}
+
+fn shape_index(name: &str) -> Result<u8, String> {
+    SHAPES
+        .iter()
+        .position(|&s| s == name)
+        .map(|i| i as u8)
+        .ok_or_else(|| format!("Unknown cell shape {}", name))
+}
+
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+impl Config {
+    /// Encode as a permalink code, suitable for a URL hash.
+    pub fn encode(&self) -> Result<String, String> {
+        let mut flags = 0;
+        if self.links {
+            flags |= LINKS;
+        }
+        if self.random_distribution.is_some() {
+            flags |= DISTRIBUTION;
+        }
+        if self.seed.is_some() {
+            flags |= SEED;
+        }
+        if self.family == RuleFamily::SecondOrder {
+            flags |= SECOND_ORDER;
+        }
+        if self.boundary == Boundary::Periodic {
+            flags |= PERIODIC;
+        }
+        if self.initial_row.is_some() {
+            flags |= INITIAL_ROW;
+        }
+
+        let mut out = vec![VERSION, self.rule, flags];
+        push_varint(&mut out, self.width as u64);
+        push_varint(&mut out, self.generations as u64);
+        push_varint(&mut out, self.scale as u64);
+        if let Some(p) = self.random_distribution {
+            out.extend_from_slice(&p.to_le_bytes());
+        }
+        if let Some(seed) = self.seed {
+            push_varint(&mut out, seed);
+        }
+        out.push(self.seed_scheme.version());
+        out.push(shape_index(&self.alive_shape)?);
+        out.push(shape_index(&self.dead_shape)?);
+        for color in [self.dead_from, self.dead_to, self.alive_from, self.alive_to] {
+            out.extend_from_slice(&color.0);
+        }
+        if let Some(row) = &self.initial_row {
+            if row.len() != self.width {
+                return Err("Initial row must match the width".to_string());
+            }
+            let mut packed = vec![0u8; row.len().div_ceil(8)];
+            for (x, &cell) in row.iter().enumerate() {
+                packed[x / 8] |= cell.min(1) << (7 - x % 8);
+            }
+            out.extend_from_slice(&packed);
+        }
+        Ok(base64url_encode(&out))
+    }
+
+    /// Decode a permalink code, or a URL whose hash is one.
+    pub fn decode(code: &str) -> Result<Self, String> {
+        let code = code.rsplit('#').next().unwrap_or(code).trim();
+        let data = base64url_decode(code)?;
+        let mut reader = Reader {
+            data: &data,
+            pos: 0,
+        };
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(format!("Unsupported permalink version {}", version));
+        }
+        let rule = reader.u8()?;
+        let flags = reader.u8()?;
+        let width = reader.varint()?;
+        let generations = reader.varint()?;
+        let scale = reader.varint()?;
+        if width == 0 || generations == 0 || scale == 0 {
+            return Err("Permalink has an empty size".to_string());
+        }
+        // The image must fit in a PNG and in memory, so decoded configs are safe to render
+        let dimension = |cells: u64| cells.checked_mul(scale).filter(|&d| d <= u32::MAX as u64);
+        dimension(width)
+            .zip(dimension(generations))
+            .and_then(|(w, h)| w.checked_mul(h)?.checked_mul(4))
+            .filter(|&bytes| bytes <= isize::MAX as u64 && usize::try_from(bytes).is_ok())
+            .ok_or("Permalink image is too large")?;
+        let (width, generations, scale) = (width as usize, generations as usize, scale as usize);
+        let random_distribution = match flags & DISTRIBUTION {
+            0 => None,
+            _ => Some(f64::from_le_bytes(
+                reader.take(8)?.try_into().expect("Slice is 8 bytes"),
+            )),
+        };
+        let seed = match flags & SEED {
+            0 => None,
+            _ => Some(reader.varint()?),
+        };
+        let scheme = reader.u8()?;
+        let seed_scheme = SeedScheme::from_version(scheme)
+            .ok_or_else(|| format!("Unknown seed scheme {}", scheme))?;
+        let mut shape = || -> Result<String, String> {
+            let index = reader.u8()?;
+            SHAPES
+                .get(index as usize)
+                .map(|s| s.to_string())
+                .ok_or_else(|| format!("Unknown cell shape {}", index))
+        };
+        let alive_shape = shape()?;
+        let dead_shape = shape()?;
+        let mut colors = [Rgb([0u8; 3]); 4];
+        for color in colors.iter_mut() {
+            color.0.copy_from_slice(reader.take(3)?);
+        }
+        let initial_row = match flags & INITIAL_ROW {
+            0 => None,
+            _ => {
+                let packed = reader.take(width.div_ceil(8))?;
+                Some(
+                    (0..width)
+                        .map(|x| (packed[x / 8] >> (7 - x % 8)) & 1)
+                        .collect(),
+                )
+            }
+        };
+        if reader.pos != data.len() {
+            return Err("Permalink has trailing data".to_string());
+        }
+        Ok(Config {
+            rule,
+            random_distribution,
+            width,
+            generations,
+            seed,
+            seed_scheme,
+            family: if flags & SECOND_ORDER != 0 {
+                RuleFamily::SecondOrder
+            } else {
+                RuleFamily::Elementary
+            },
+            boundary: if flags & PERIODIC != 0 {
+                Boundary::Periodic
+            } else {
+                Boundary::Dead
+            },
+            initial_row,
+            scale,
+            alive_shape,
+            dead_shape,
+            links: flags & LINKS != 0,
+            dead_from: colors[0],
+            dead_to: colors[1],
+            alive_from: colors[2],
+            alive_to: colors[3],
+        })
+    }
+
+    /// Read the permalink embedded in a PNG under `PNG_KEYWORD`.
+    pub fn from_png(data: &[u8]) -> Result<Self, String> {
+        let reader = png::Decoder::new(std::io::Cursor::new(data))
+            .read_info()
+            .map_err(|e| format!("Failed to read PNG: {}", e))?;
+        let chunk = reader
+            .info()
+            .uncompressed_latin1_text
+            .iter()
+            .find(|chunk| chunk.keyword == PNG_KEYWORD)
+            .ok_or("PNG has no permalink")?;
+        Self::decode(&chunk.text)
+    }
+}
+
+/// Reads fields from a permalink in order, failing cleanly if it is truncated.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or("Permalink is truncated")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Permalink has an overlong number".to_string())
+    }
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64url_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(BASE64URL[(bits >> (18 - 6 * i)) as usize & 63] as char);
+        }
+    }
+    out
+}
+
+fn base64url_decode(s: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+    for c in s.bytes() {
+        let value = BASE64URL
+            .iter()
+            .position(|&b| b == c)
+            .ok_or_else(|| format!("Invalid permalink character '{}'", c as char))?;
+        bits = (bits << 6) | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Ok(out)
+}
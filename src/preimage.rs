@@ -0,0 +1,185 @@
+use crate::Boundary;
+use alloc::{vec, vec::Vec};
+
+// Preimages are found on the de Bruijn graph of overlapping cell pairs. A row `x`
+// of width `n` is extended to `y` of length `n + 2` with the boundary cells on
+// each side, and `y` is a preimage of `target` when `rule(y[i], y[i+1], y[i+2])`
+// equals `target[i]` for every `i`. Dead boundaries pin the outer cells to 0;
+// periodic ones pin them to the opposite end of the row.
+
+/// Value that the extended row must take at position `j`, given the first two cells.
+fn forced(boundary: Boundary, n: usize, j: usize, y0: u8, y1: u8) -> Option<u8> {
+    match boundary {
+        Boundary::Dead if j == 0 || j == n + 1 => Some(0),
+        Boundary::Periodic if j == n => Some(y0),
+        Boundary::Periodic if j == n + 1 => Some(y1),
+        _ => None,
+    }
+}
+
+fn allowed(boundary: Boundary, n: usize, j: usize, y0: u8, y1: u8, value: u8) -> bool {
+    forced(boundary, n, j, y0, y1).is_none_or(|v| v == value)
+}
+
+/// Transfer-matrix counts for one choice of the first two extended cells.
+///
+/// `counts[j][(a << 1) | b]` is the number of ways to complete the extended row
+/// from position `j` onward when `y[j] = a` and `y[j + 1] = b`.
+fn completion_counts(
+    rule: u8,
+    target: &[u8],
+    boundary: Boundary,
+    y0: u8,
+    y1: u8,
+) -> Vec<[u128; 4]> {
+    let n = target.len();
+    let mut counts = vec![[0u128; 4]; n + 1];
+    for state in 0..4u8 {
+        let (a, b) = (state >> 1, state & 1);
+        if allowed(boundary, n, n, y0, y1, a) && allowed(boundary, n, n + 1, y0, y1, b) {
+            counts[n][state as usize] = 1;
+        }
+    }
+    for j in (0..n).rev() {
+        for state in 0..4u8 {
+            let (a, b) = (state >> 1, state & 1);
+            let mut total = 0u128;
+            for c in 0..2u8 {
+                let idx = (a << 2) | (b << 1) | c;
+                if (rule >> idx) & 1 == target[j] && allowed(boundary, n, j + 2, y0, y1, c) {
+                    total = total.saturating_add(counts[j + 1][((b << 1) | c) as usize]);
+                }
+            }
+            counts[j][state as usize] = total;
+        }
+    }
+    counts
+}
+
+/// Starting pairs `(y[0], y[1])` consistent with the boundary.
+fn start_pairs(boundary: Boundary, n: usize) -> Vec<(u8, u8)> {
+    let mut pairs = Vec::new();
+    for y0 in 0..2u8 {
+        for y1 in 0..2u8 {
+            if allowed(boundary, n, 0, y0, y1, y0) && allowed(boundary, n, 1, y0, y1, y1) {
+                pairs.push((y0, y1));
+            }
+        }
+    }
+    pairs
+}
+
+/// Number of rows that evolve into `target` in one step (saturates at `u128::MAX`).
+pub fn count_preimages(rule: u8, target: &[u8], boundary: Boundary) -> u128 {
+    if target.is_empty() {
+        return 1;
+    }
+    start_pairs(boundary, target.len())
+        .into_iter()
+        .map(|(y0, y1)| {
+            let counts = completion_counts(rule, target, boundary, y0, y1);
+            counts[0][((y0 << 1) | y1) as usize]
+        })
+        .fold(0u128, |acc, c| acc.saturating_add(c))
+}
+
+/// Whether `target` has no preimage at all (a Garden-of-Eden row).
+pub fn is_garden_of_eden(rule: u8, target: &[u8], boundary: Boundary) -> bool {
+    count_preimages(rule, target, boundary) == 0
+}
+
+/// Up to `limit` rows that evolve into `target` in one step, in lexicographic order for
+/// dead boundaries. Periodic ones list rows ending in a dead cell first.
+pub fn find_preimages(rule: u8, target: &[u8], boundary: Boundary, limit: usize) -> Vec<Vec<u8>> {
+    let n = target.len();
+    let mut found = Vec::new();
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    for (y0, y1) in start_pairs(boundary, n) {
+        let counts = completion_counts(rule, target, boundary, y0, y1);
+        if counts[0][((y0 << 1) | y1) as usize] == 0 {
+            continue;
+        }
+        let mut y = vec![y0, y1];
+        collect_preimages(rule, target, &counts, boundary, &mut y, limit, &mut found);
+        if found.len() >= limit {
+            break;
+        }
+    }
+    found
+}
+
+/// Depth-first walk along transitions with a non-zero completion count.
+fn collect_preimages(
+    rule: u8,
+    target: &[u8],
+    counts: &[[u128; 4]],
+    boundary: Boundary,
+    y: &mut Vec<u8>,
+    limit: usize,
+    found: &mut Vec<Vec<u8>>,
+) {
+    let n = target.len();
+    if found.len() >= limit {
+        return;
+    }
+    let j = y.len() - 2;
+    if j == n {
+        found.push(y[1..=n].to_vec());
+        return;
+    }
+    let (a, b) = (y[j], y[j + 1]);
+    for c in 0..2u8 {
+        let idx = (a << 2) | (b << 1) | c;
+        if (rule >> idx) & 1 == target[j]
+            && allowed(boundary, n, j + 2, y[0], y[1], c)
+            && counts[j + 1][((b << 1) | c) as usize] > 0
+        {
+            y.push(c);
+            collect_preimages(rule, target, counts, boundary, y, limit, found);
+            y.pop();
+        }
+    }
+}
+
+/// Reconstruct a history of `generations` rows ending in `target`, oldest first.
+///
+/// Backtracks through at most `branching` preimages per generation and returns
+/// `None` if every branch runs into a Garden-of-Eden row.
+pub fn backtrack(
+    rule: u8,
+    target: &[u8],
+    boundary: Boundary,
+    generations: usize,
+    branching: usize,
+) -> Option<Vec<Vec<u8>>> {
+    let mut history = vec![target.to_vec()];
+    if backtrack_from(rule, boundary, generations, branching, &mut history) {
+        history.reverse();
+        Some(history)
+    } else {
+        None
+    }
+}
+
+fn backtrack_from(
+    rule: u8,
+    boundary: Boundary,
+    generations: usize,
+    branching: usize,
+    history: &mut Vec<Vec<u8>>,
+) -> bool {
+    if history.len() >= generations {
+        return true;
+    }
+    let current = history.last().expect("History is never empty").clone();
+    for preimage in find_preimages(rule, &current, boundary, branching) {
+        history.push(preimage);
+        if backtrack_from(rule, boundary, generations, branching, history) {
+            return true;
+        }
+        history.pop();
+    }
+    false
+}
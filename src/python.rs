@@ -0,0 +1,190 @@
+//! Python bindings, built as the `ca` extension module by maturin. Rows and images are
+//! returned as NumPy arrays and match the CLI and web UI for the same options and seed.
+
+use crate::image_output::{try_parse_hex_color, CellShape, RgbaRenderer};
+use crate::{Automaton, Boundary, RuleFamily, SeedScheme};
+use image::Rgb;
+use numpy::{PyArray1, PyArray2, PyArray3, PyArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// A `ValueError` unless `ok`, for arguments the engine would otherwise panic on.
+fn check(ok: bool, message: &str) -> PyResult<()> {
+    if ok {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err(message.to_string()))
+    }
+}
+
+/// `value`, or a `ValueError` naming the unknown `kind` of option.
+fn known<T>(value: Option<T>, kind: &str, name: &str) -> PyResult<T> {
+    value.ok_or_else(|| PyValueError::new_err(format!("Unknown {}: {}", kind, name)))
+}
+
+/// A `#rrggbb` colour, or a `ValueError` if it is malformed.
+fn color(s: &str) -> PyResult<Rgb<u8>> {
+    try_parse_hex_color(s)
+        .ok_or_else(|| PyValueError::new_err(format!("Color must be in format #RRGGBB: {}", s)))
+}
+
+/// The automaton described by the options shared by `run` and `render`. An initial row
+/// replaces the random distribution and width, as the CLI's `--init` does.
+fn automaton(
+    rule: u8,
+    width: usize,
+    random_distribution: Option<f64>,
+    seed: Option<u64>,
+    seed_scheme: u8,
+    boundary: &str,
+    family: &str,
+    initial_row: Option<Vec<u8>>,
+) -> PyResult<Automaton> {
+    let scheme = SeedScheme::from_version(seed_scheme)
+        .ok_or_else(|| PyValueError::new_err("Seed scheme must be 1, 2 or 3"))?;
+    let boundary = known(Boundary::from_name(boundary), "boundary", boundary)?;
+    let family = known(RuleFamily::from_name(family), "rule family", family)?;
+    let width = initial_row.as_ref().map_or(width, Vec::len);
+    check(width > 0, "Width must be at least 1")?;
+    let automaton = match initial_row {
+        Some(row) => Automaton::from_row(rule, row.iter().map(|&c| c.min(1)).collect())
+            .with_seed_scheme(seed, scheme),
+        None => Automaton::new_with_scheme(rule, random_distribution, width, seed, scheme),
+    };
+    Ok(automaton.with_boundary(boundary).with_family(family))
+}
+
+/// Run an automaton and return its generations as a `(generations, width)` array of 0s
+/// and 1s. A random distribution of `None` starts from a single centre cell.
+#[pyfunction]
+#[pyo3(signature = (
+    rule,
+    width = 64,
+    generations = 32,
+    random_distribution = None,
+    seed = None,
+    seed_scheme = 2,
+    boundary = "dead",
+    family = "elementary",
+    initial_row = None,
+))]
+fn run<'py>(
+    py: Python<'py>,
+    rule: u8,
+    width: usize,
+    generations: usize,
+    random_distribution: Option<f64>,
+    seed: Option<u64>,
+    seed_scheme: u8,
+    boundary: &str,
+    family: &str,
+    initial_row: Option<Vec<u8>>,
+) -> PyResult<Bound<'py, PyArray2<u8>>> {
+    check(generations > 0, "A run needs at least one generation")?;
+    let mut automaton = automaton(
+        rule,
+        width,
+        random_distribution,
+        seed,
+        seed_scheme,
+        boundary,
+        family,
+        initial_row,
+    )?;
+    let width = automaton.row().len();
+    check(
+        width.checked_mul(generations).is_some(),
+        "The run is too large",
+    )?;
+    PyArray1::from_vec(py, automaton.run(generations)).reshape([generations, width])
+}
+
+/// Draw a run as a `(height, width, 4)` RGBA array, e.g. for `PIL.Image.fromarray`.
+/// Takes the options of `run` plus the CLI's image options, with the same defaults.
+#[pyfunction]
+#[pyo3(signature = (
+    rule,
+    width = 64,
+    generations = 32,
+    random_distribution = None,
+    seed = None,
+    seed_scheme = 2,
+    boundary = "dead",
+    family = "elementary",
+    initial_row = None,
+    scale = 1,
+    alive_shape = "square",
+    dead_shape = "square",
+    links = false,
+    dead_color_from = "#ffaaff",
+    dead_color_to = "#000000",
+    alive_color_from = "#000000",
+    alive_color_to = "#aaffff",
+))]
+fn render<'py>(
+    py: Python<'py>,
+    rule: u8,
+    width: usize,
+    generations: usize,
+    random_distribution: Option<f64>,
+    seed: Option<u64>,
+    seed_scheme: u8,
+    boundary: &str,
+    family: &str,
+    initial_row: Option<Vec<u8>>,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    links: bool,
+    dead_color_from: &str,
+    dead_color_to: &str,
+    alive_color_from: &str,
+    alive_color_to: &str,
+) -> PyResult<Bound<'py, PyArray3<u8>>> {
+    check(generations > 0, "A run needs at least one generation")?;
+    check(scale > 0, "Scale must be at least 1")?;
+    let mut automaton = automaton(
+        rule,
+        width,
+        random_distribution,
+        seed,
+        seed_scheme,
+        boundary,
+        family,
+        initial_row,
+    )?;
+    let width = automaton.row().len();
+    let size = width
+        .checked_mul(scale)
+        .and_then(|w| w.checked_mul(generations))
+        .and_then(|n| n.checked_mul(scale))
+        .and_then(|n| n.checked_mul(4));
+    check(size.is_some(), "The image is too large")?;
+    let mut renderer = RgbaRenderer::new(
+        Vec::new(),
+        width,
+        generations,
+        scale,
+        known(CellShape::from_name(alive_shape), "cell shape", alive_shape)?,
+        known(CellShape::from_name(dead_shape), "cell shape", dead_shape)?,
+        links,
+        color(dead_color_from)?,
+        color(dead_color_to)?,
+        color(alive_color_from)?,
+        color(alive_color_to)?,
+    );
+    renderer.push_row(automaton.row());
+    for _ in 1..generations {
+        automaton.step();
+        renderer.push_row(automaton.row());
+    }
+    PyArray1::from_vec(py, renderer.into_buffer()).reshape([generations * scale, width * scale, 4])
+}
+
+#[pymodule]
+#[pyo3(name = "ca")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(run, m)?)?;
+    m.add_function(wrap_pyfunction!(render, m)?)?;
+    Ok(())
+}
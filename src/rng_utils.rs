@@ -1,16 +1,260 @@
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "std")]
 use getrandom::getrandom;
-use rand::rngs::SmallRng;
-use rand::SeedableRng;
 
-/// Returns a seeded SmallRng using OS randomness or a provided u64 seed.
-pub fn seeded_small_rng(seed: Option<u64>) -> SmallRng {
-    let mut seed_arr = [0u8; 32];
-    if let Some(s) = seed {
-        // Fill the first 8 bytes with the u64 seed, rest zero
-        seed_arr[..8].copy_from_slice(&s.to_le_bytes());
-    } else {
-        getrandom(&mut seed_arr).expect("Failed to get randomness from OS");
+/// How a `u64` seed is turned into a generator. Every scheme is fixed forever, so a
+/// published seed reproduces the same run on any platform as long as its scheme is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedScheme {
+    /// Xoshiro256++ with the seed in the low 8 bytes of the state and the rest zero.
+    /// This is what `SmallRng` produced on 64-bit targets before schemes existed.
+    V1 = 1,
+    /// PCG64 (XSL RR 128/64) with all 256 bits of state and stream expanded from the
+    /// seed by SplitMix64.
+    V2 = 2,
+    /// Xoshiro128++ with the seed in the low 8 bytes of the state and the rest zero.
+    /// This is what `SmallRng` produced on 32-bit targets, including the web UI's
+    /// wasm32 build, before schemes existed.
+    V1Wasm = 3,
+}
+
+impl SeedScheme {
+    /// The scheme used when none is requested.
+    pub const LATEST: SeedScheme = SeedScheme::V2;
+
+    pub fn from_version(version: u8) -> Option<Self> {
+        match version {
+            1 => Some(SeedScheme::V1),
+            2 => Some(SeedScheme::V2),
+            3 => Some(SeedScheme::V1Wasm),
+            _ => None,
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        s.trim_start_matches(['v', 'V'])
+            .parse()
+            .ok()
+            .and_then(Self::from_version)
+            .expect("Seed scheme must be 1, 2 or 3")
+    }
+
+    pub fn version(self) -> u8 {
+        self as u8
+    }
+}
+
+/// SplitMix64 output sequence starting after `state`.
+fn splitmix64(mut state: u64) -> [u64; 4] {
+    const PHI: u64 = 0x9e3779b97f4a7c15;
+    let mut words = [0u64; 4];
+    for word in words.iter_mut() {
+        state = state.wrapping_add(PHI);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        *word = z ^ (z >> 31);
+    }
+    words
+}
+
+/// The PCG64 LCG multiplier, as in the reference implementation and `rand_pcg`.
+const PCG_MULTIPLIER: u128 = 0x2360_ED05_1FC6_5DA4_4385_DF64_9FCC_F645;
+
+/// One step of Xoshiro128++, returning its 32-bit output.
+fn xoshiro128_next(s: &mut [u32; 4]) -> u32 {
+    let res = s[0].wrapping_add(s[3]).rotate_left(7).wrapping_add(s[0]);
+    let t = s[1] << 9;
+    s[2] ^= s[0];
+    s[3] ^= s[1];
+    s[1] ^= s[2];
+    s[0] ^= s[3];
+    s[2] ^= t;
+    s[3] = s[3].rotate_left(11);
+    res
+}
+
+/// Xoshiro128++ state from two words, low halves first.
+fn xoshiro128_state(lo: u64, hi: u64) -> [u32; 4] {
+    [lo as u32, (lo >> 32) as u32, hi as u32, (hi >> 32) as u32]
+}
+
+/// Simulation RNG. The algorithm depends on the seed scheme; all are implemented
+/// in-crate so results never change with dependency upgrades, and the state can be
+/// saved in checkpoints and restored exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimRng {
+    /// Xoshiro256++ (seed scheme 1).
+    Xoshiro256PlusPlus([u64; 4]),
+    /// PCG64 XSL RR 128/64 (seed scheme 2). `increment` is always odd.
+    Pcg64 { state: u128, increment: u128 },
+    /// Xoshiro128++ (seed scheme 3).
+    Xoshiro128PlusPlus([u32; 4]),
+}
+
+impl SimRng {
+    /// Seed `scheme`'s generator from a `u64`, as documented on `SeedScheme`.
+    pub fn from_u64(seed: u64, scheme: SeedScheme) -> Self {
+        match scheme {
+            SeedScheme::V1 => {
+                let mut s = [seed, 0, 0, 0];
+                if seed == 0 {
+                    // Xoshiro must not start from all zeros; SmallRng fell back to this
+                    s = splitmix64(0);
+                }
+                SimRng::Xoshiro256PlusPlus(s)
+            }
+            SeedScheme::V2 => {
+                let w = splitmix64(seed);
+                let state = w[0] as u128 | (w[1] as u128) << 64;
+                let stream = w[2] as u128 | (w[3] as u128) << 64;
+                SimRng::pcg64(state, stream)
+            }
+            SeedScheme::V1Wasm => {
+                let mut s = xoshiro128_state(seed, 0);
+                if seed == 0 {
+                    // As for scheme 1, with SplitMix64's outputs split into 32-bit words
+                    let w = splitmix64(0);
+                    s = xoshiro128_state(w[0], w[1]);
+                }
+                SimRng::Xoshiro128PlusPlus(s)
+            }
+        }
+    }
+
+    /// Seed `scheme`'s generator from fresh OS randomness.
+    #[cfg(feature = "std")]
+    pub fn from_entropy(scheme: SeedScheme) -> Self {
+        let mut bytes = [0u8; 32];
+        getrandom(&mut bytes).expect("Failed to get randomness from OS");
+        let mut w = [0u64; 4];
+        for (word, chunk) in w.iter_mut().zip(bytes.chunks_exact(8)) {
+            *word = u64::from_le_bytes(chunk.try_into().expect("Chunk is 8 bytes"));
+        }
+        match scheme {
+            SeedScheme::V1 if w.iter().all(|&x| x == 0) => Self::from_u64(0, scheme),
+            SeedScheme::V1 => SimRng::Xoshiro256PlusPlus(w),
+            SeedScheme::V2 => SimRng::pcg64(
+                w[0] as u128 | (w[1] as u128) << 64,
+                w[2] as u128 | (w[3] as u128) << 64,
+            ),
+            SeedScheme::V1Wasm if w[0] == 0 && w[1] == 0 => Self::from_u64(0, scheme),
+            SeedScheme::V1Wasm => SimRng::Xoshiro128PlusPlus(xoshiro128_state(w[0], w[1])),
+        }
+    }
+
+    /// PCG64 on stream `stream`, advanced once past `state` as the reference code does.
+    fn pcg64(state: u128, stream: u128) -> Self {
+        let increment = (stream << 1) | 1;
+        let mut state = state.wrapping_add(increment);
+        state = state.wrapping_mul(PCG_MULTIPLIER).wrapping_add(increment);
+        SimRng::Pcg64 { state, increment }
+    }
+
+    /// Restore a generator from a state returned by `state`.
+    #[cfg(feature = "alloc")]
+    pub fn from_state(scheme: SeedScheme, words: [u64; 4]) -> Result<Self, String> {
+        match scheme {
+            SeedScheme::V1 if words.iter().all(|&x| x == 0) => {
+                Err("Xoshiro state must not be all zero".to_string())
+            }
+            SeedScheme::V1 => Ok(SimRng::Xoshiro256PlusPlus(words)),
+            SeedScheme::V2 if words[2] & 1 == 0 => Err("PCG64 increment must be odd".to_string()),
+            SeedScheme::V2 => Ok(SimRng::Pcg64 {
+                state: words[0] as u128 | (words[1] as u128) << 64,
+                increment: words[2] as u128 | (words[3] as u128) << 64,
+            }),
+            SeedScheme::V1Wasm if words[0] == 0 && words[1] == 0 => {
+                Err("Xoshiro state must not be all zero".to_string())
+            }
+            SeedScheme::V1Wasm if words[2] != 0 || words[3] != 0 => {
+                Err("Xoshiro128++ state only has two words".to_string())
+            }
+            SeedScheme::V1Wasm => Ok(SimRng::Xoshiro128PlusPlus(xoshiro128_state(
+                words[0], words[1],
+            ))),
+        }
+    }
+
+    /// The seed scheme and full internal state as four words. Xoshiro128++ packs its
+    /// state into the first two, low halves first.
+    #[cfg(feature = "alloc")]
+    pub fn state(&self) -> (SeedScheme, [u64; 4]) {
+        match *self {
+            SimRng::Xoshiro256PlusPlus(s) => (SeedScheme::V1, s),
+            SimRng::Pcg64 { state, increment } => (
+                SeedScheme::V2,
+                [
+                    state as u64,
+                    (state >> 64) as u64,
+                    increment as u64,
+                    (increment >> 64) as u64,
+                ],
+            ),
+            SimRng::Xoshiro128PlusPlus(s) => (
+                SeedScheme::V1Wasm,
+                [
+                    s[0] as u64 | (s[1] as u64) << 32,
+                    s[2] as u64 | (s[3] as u64) << 32,
+                    0,
+                    0,
+                ],
+            ),
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        match self {
+            SimRng::Xoshiro256PlusPlus(s) => {
+                let res = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+                let t = s[1] << 17;
+                s[2] ^= s[0];
+                s[3] ^= s[1];
+                s[1] ^= s[2];
+                s[0] ^= s[3];
+                s[2] ^= t;
+                s[3] = s[3].rotate_left(45);
+                res
+            }
+            SimRng::Pcg64 { state, increment } => {
+                *state = state.wrapping_mul(PCG_MULTIPLIER).wrapping_add(*increment);
+                let rot = (*state >> 122) as u32;
+                ((*state >> 64) as u64 ^ *state as u64).rotate_right(rot)
+            }
+            SimRng::Xoshiro128PlusPlus(s) => {
+                // Two outputs, low half first, as `rand` builds a u64 from a 32-bit generator
+                let lo = xoshiro128_next(s) as u64;
+                lo | (xoshiro128_next(s) as u64) << 32
+            }
+        }
+    }
+
+    /// The upper half of `next_u64`, whose low bits are weaker for Xoshiro256++, or the
+    /// next output of the 32-bit Xoshiro128++.
+    pub fn next_u32(&mut self) -> u32 {
+        match self {
+            SimRng::Xoshiro128PlusPlus(s) => xoshiro128_next(s),
+            _ => (self.next_u64() >> 32) as u32,
+        }
     }
-    SmallRng::from_seed(seed_arr)
 }
 
+/// Returns a SimRng for `scheme` using OS randomness or a provided u64 seed.
+/// Without the `std` feature there is no OS randomness and a seed is required.
+#[cfg(feature = "alloc")]
+pub fn seeded_rng(seed: Option<u64>, scheme: SeedScheme) -> SimRng {
+    match seed {
+        Some(s) => SimRng::from_u64(s, scheme),
+        #[cfg(feature = "std")]
+        None => SimRng::from_entropy(scheme),
+        #[cfg(not(feature = "std"))]
+        None => panic!("A seed is required without the std feature"),
+    }
+}
+
+/// Draw a uniform float in [0, 1) from the RNG.
+pub fn next_unit(rng: &mut SimRng) -> f64 {
+    // Use next_u32 for randomness, convert to [0,1)
+    (rng.next_u32() as f64) / (u32::MAX as f64 + 1.0)
+}
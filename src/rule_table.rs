@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+/// Explicit transition table of an elementary rule.
+///
+/// `outputs[idx]` is the new state for the neighbourhood `idx = (l << 2) | (c << 1) | r`,
+/// or `None` if the table does not say.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleTable {
+    pub outputs: [Option<u8>; 8],
+}
+
+impl RuleTable {
+    /// Table of a numbered elementary rule.
+    pub fn from_rule(rule: u8) -> Self {
+        let mut outputs = [None; 8];
+        for (idx, out) in outputs.iter_mut().enumerate() {
+            *out = Some((rule >> idx) & 1);
+        }
+        RuleTable { outputs }
+    }
+
+    /// Rule number of the table, or `None` if some neighbourhood is unspecified.
+    pub fn to_rule(&self) -> Option<u8> {
+        let mut rule = 0u8;
+        for (idx, out) in self.outputs.iter().enumerate() {
+            rule |= (*out)? << idx;
+        }
+        Some(rule)
+    }
+
+    /// Parse a transition string such as `111:0 110:1 101:1 100:0 011:1 010:1 001:1 000:0`.
+    ///
+    /// Entries are separated by whitespace or commas, and may appear in any order.
+    pub fn parse_transitions(s: &str) -> Result<Self, String> {
+        let mut outputs = [None; 8];
+        for entry in s.split(|c: char| c.is_whitespace() || c == ',') {
+            if entry.is_empty() {
+                continue;
+            }
+            let (neighbourhood, out) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("Transition '{}' must look like 110:1", entry))?;
+            if neighbourhood.len() != 3 {
+                return Err(format!(
+                    "Neighbourhood '{}' must have 3 cells",
+                    neighbourhood
+                ));
+            }
+            let idx = parse_bits(neighbourhood)?;
+            let out = parse_bits(out)?;
+            if out > 1 {
+                return Err(format!("Output '{}' must be 0 or 1", out));
+            }
+            match outputs[idx as usize] {
+                Some(prev) if prev != out => {
+                    return Err(format!("Conflicting outputs for {}", neighbourhood));
+                }
+                _ => outputs[idx as usize] = Some(out),
+            }
+        }
+        Ok(RuleTable { outputs })
+    }
+
+    /// Parse a Golly rule table (`.table`, or a `.rule` file with an `@TABLE` section)
+    /// using the `oneDimensional` neighbourhood.
+    ///
+    /// Transitions are `C,W,E,C'` with optional `var` declarations, and the first
+    /// matching transition wins. Neighbourhoods with no matching transition keep
+    /// their state, as in Golly, so the result is always a complete table.
+    pub fn parse_golly(s: &str) -> Result<Self, String> {
+        let mut lines: Vec<&str> = s.lines().collect();
+        if let Some(start) = lines.iter().position(|l| l.trim() == "@TABLE") {
+            lines = lines[start + 1..]
+                .iter()
+                .take_while(|l| !l.trim_start().starts_with('@'))
+                .copied()
+                .collect();
+        }
+
+        let mut vars: HashMap<String, Vec<u8>> = HashMap::new();
+        let mut reflect = false;
+        let mut outputs = [None; 8];
+        for line in lines {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "n_states" if value != "2" => {
+                        return Err(format!("Only 2-state tables are supported, got {}", value));
+                    }
+                    "neighborhood" if value != "oneDimensional" => {
+                        return Err(format!("Unsupported neighborhood '{}'", value));
+                    }
+                    "symmetries" => match value {
+                        "none" => reflect = false,
+                        "reflect" => reflect = true,
+                        _ => return Err(format!("Unsupported symmetries '{}'", value)),
+                    },
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some(decl) = line.strip_prefix("var ") {
+                let (name, values) = decl
+                    .split_once('=')
+                    .ok_or_else(|| format!("Malformed variable '{}'", line))?;
+                let values = values.trim().trim_start_matches('{').trim_end_matches('}');
+                let values = values
+                    .split(',')
+                    .map(|v| resolve(v.trim(), &vars))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .concat();
+                vars.insert(name.trim().to_string(), values);
+                continue;
+            }
+
+            let fields: Vec<&str> = if line.contains(',') {
+                line.split(',').map(str::trim).collect()
+            } else {
+                line.split_whitespace()
+                    .flat_map(|w| (0..w.len()).map(move |i| &w[i..i + 1]))
+                    .collect()
+            };
+            if fields.len() != 4 {
+                return Err(format!(
+                    "Transition '{}' must have 4 states (C,W,E,C')",
+                    line
+                ));
+            }
+            for (c, l, r, out) in expand(&fields, &vars)? {
+                let mut neighbourhoods = vec![(l, c, r)];
+                if reflect {
+                    neighbourhoods.push((r, c, l));
+                }
+                for (l, c, r) in neighbourhoods {
+                    let idx = ((l << 2) | (c << 1) | r) as usize;
+                    outputs[idx].get_or_insert(out);
+                }
+            }
+        }
+        for (idx, out) in outputs.iter_mut().enumerate() {
+            out.get_or_insert((idx as u8 >> 1) & 1);
+        }
+        Ok(RuleTable { outputs })
+    }
+}
+
+impl std::fmt::Display for RuleTable {
+    /// Formats as a transition string, from `111` down to `000`, with `?` for unspecified outputs.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for idx in (0..8).rev() {
+            let out = match self.outputs[idx] {
+                Some(v) => char::from(b'0' + v),
+                None => '?',
+            };
+            write!(f, "{:03b}:{}", idx, out)?;
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_bits(s: &str) -> Result<u8, String> {
+    u8::from_str_radix(s, 2).map_err(|_| format!("'{}' must only contain 0 and 1", s))
+}
+
+/// Values a Golly field can take: a state or a variable name.
+fn resolve(field: &str, vars: &HashMap<String, Vec<u8>>) -> Result<Vec<u8>, String> {
+    match field {
+        "0" => Ok(vec![0]),
+        "1" => Ok(vec![1]),
+        _ => vars
+            .get(field)
+            .cloned()
+            .ok_or_else(|| format!("Unknown state or variable '{}'", field)),
+    }
+}
+
+/// All concrete `(C, W, E, C')` transitions of a Golly line. A variable that
+/// appears more than once takes the same value everywhere on the line.
+fn expand(
+    fields: &[&str],
+    vars: &HashMap<String, Vec<u8>>,
+) -> Result<Vec<(u8, u8, u8, u8)>, String> {
+    let mut bindings: Vec<HashMap<&str, u8>> = vec![HashMap::new()];
+    let mut rows: Vec<Vec<u8>> = vec![Vec::new()];
+    for &field in fields {
+        let values = resolve(field, vars)?;
+        let is_var = vars.contains_key(field);
+        let mut next_bindings = Vec::new();
+        let mut next_rows = Vec::new();
+        for (binding, row) in bindings.iter().zip(&rows) {
+            let choices = match binding.get(field) {
+                Some(&bound) if is_var => vec![bound],
+                _ => values.clone(),
+            };
+            for value in choices {
+                let mut binding = binding.clone();
+                if is_var {
+                    binding.insert(field, value);
+                }
+                let mut row = row.clone();
+                row.push(value);
+                next_bindings.push(binding);
+                next_rows.push(row);
+            }
+        }
+        bindings = next_bindings;
+        rows = next_rows;
+    }
+    Ok(rows.into_iter().map(|r| (r[0], r[1], r[2], r[3])).collect())
+}
+
+/// Parse a rule given as a number (0-255), a transition string, or a Golly table.
+///
+/// Fails if the table leaves some neighbourhood unspecified, since it then has no rule number.
+pub fn parse_rule(s: &str) -> Result<u8, String> {
+    let trimmed = s.trim();
+    if let Ok(rule) = trimmed.parse::<u8>() {
+        return Ok(rule);
+    }
+    let table = if trimmed.contains('\n') || trimmed.starts_with('@') {
+        RuleTable::parse_golly(trimmed)?
+    } else {
+        RuleTable::parse_transitions(trimmed)?
+    };
+    table
+        .to_rule()
+        .ok_or_else(|| format!("Incomplete rule table: {}", table))
+}
@@ -0,0 +1,297 @@
+#[cfg(feature = "png")]
+use crate::image_output::draw_line_bresenham_rgba;
+#[cfg(feature = "png")]
+use image::{ImageBuffer, Rgb, Rgba};
+
+/// Fraction of alive cells in a row.
+pub fn density(row: &[u8]) -> f64 {
+    if row.is_empty() {
+        return 0.0;
+    }
+    row.iter().filter(|&&c| c == 1).count() as f64 / row.len() as f64
+}
+
+/// Shannon entropy (in bits) of the distribution of length-`block_size` blocks in a row.
+///
+/// Blocks are read with a sliding window and do not wrap around the edges.
+pub fn block_entropy(row: &[u8], block_size: usize) -> f64 {
+    assert!(
+        (1..=20).contains(&block_size),
+        "Block size must be between 1 and 20"
+    );
+    if row.len() < block_size {
+        return 0.0;
+    }
+    let mask = (1usize << block_size) - 1;
+    let mut counts = vec![0usize; 1 << block_size];
+    let mut code = 0usize;
+    for (i, &cell) in row.iter().enumerate() {
+        code = ((code << 1) | cell as usize) & mask;
+        if i + 1 >= block_size {
+            counts[code] += 1;
+        }
+    }
+    let total = (row.len() - block_size + 1) as f64;
+    counts
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Pearson correlation between two equally long cell sequences.
+///
+/// Returns `None` when either sequence is constant, since the correlation is undefined.
+pub fn correlation(a: &[u8], b: &[u8]) -> Option<f64> {
+    assert_eq!(a.len(), b.len(), "Sequences must have the same length");
+    let n = a.len() as f64;
+    if a.is_empty() {
+        return None;
+    }
+    let (mut sum_a, mut sum_b, mut sum_ab) = (0usize, 0usize, 0usize);
+    for (&x, &y) in a.iter().zip(b) {
+        sum_a += x as usize;
+        sum_b += y as usize;
+        sum_ab += (x & y) as usize;
+    }
+    let mean_a = sum_a as f64 / n;
+    let mean_b = sum_b as f64 / n;
+    // Cells are 0/1, so E[x^2] == E[x]
+    let var_a = mean_a - mean_a * mean_a;
+    let var_b = mean_b - mean_b * mean_b;
+    if var_a <= 0.0 || var_b <= 0.0 {
+        return None;
+    }
+    Some((sum_ab as f64 / n - mean_a * mean_b) / (var_a * var_b).sqrt())
+}
+
+/// Spatial autocorrelation of a row at the given lag (no wrap-around).
+pub fn spatial_correlation(row: &[u8], lag: usize) -> Option<f64> {
+    if lag >= row.len() {
+        return None;
+    }
+    correlation(&row[..row.len() - lag], &row[lag..])
+}
+
+/// Statistics for a single generation.
+#[derive(Debug, Clone)]
+pub struct GenerationStats {
+    pub generation: usize,
+    /// Fraction of alive cells.
+    pub density: f64,
+    /// `(block size, entropy in bits)` for each configured block size.
+    pub block_entropy: Vec<(usize, f64)>,
+    /// `(lag, autocorrelation)` for each configured lag.
+    pub spatial_correlation: Vec<(usize, Option<f64>)>,
+    /// Correlation with the previous generation (`None` for the first row).
+    pub temporal_correlation: Option<f64>,
+    /// Fraction of generations so far in which the centre cell was alive.
+    pub centre_density: f64,
+}
+
+/// Accumulates statistics over a stream of generations.
+pub struct StatsCollector {
+    block_sizes: Vec<usize>,
+    lags: Vec<usize>,
+    previous: Vec<u8>,
+    centre_alive: usize,
+    generation: usize,
+}
+
+impl StatsCollector {
+    pub fn new(block_sizes: &[usize], lags: &[usize]) -> Self {
+        StatsCollector {
+            block_sizes: block_sizes.to_vec(),
+            lags: lags.to_vec(),
+            previous: Vec::new(),
+            centre_alive: 0,
+            generation: 0,
+        }
+    }
+
+    /// Compute the statistics for the next row of the run.
+    pub fn push_row(&mut self, row: &[u8]) -> GenerationStats {
+        if row.get(row.len() / 2) == Some(&1) {
+            self.centre_alive += 1;
+        }
+        let temporal_correlation = if self.generation == 0 {
+            None
+        } else {
+            correlation(&self.previous, row)
+        };
+        let stats = GenerationStats {
+            generation: self.generation,
+            density: density(row),
+            block_entropy: self
+                .block_sizes
+                .iter()
+                .map(|&k| (k, block_entropy(row, k)))
+                .collect(),
+            spatial_correlation: self
+                .lags
+                .iter()
+                .map(|&lag| (lag, spatial_correlation(row, lag)))
+                .collect(),
+            temporal_correlation,
+            centre_density: self.centre_alive as f64 / (self.generation + 1) as f64,
+        };
+        self.previous.clear();
+        self.previous.extend_from_slice(row);
+        self.generation += 1;
+        stats
+    }
+
+    /// CSV header matching `GenerationStats::to_csv`.
+    pub fn csv_header(&self) -> String {
+        let mut cols = vec!["generation".to_string(), "density".to_string()];
+        cols.extend(self.block_sizes.iter().map(|k| format!("entropy_{}", k)));
+        cols.extend(self.lags.iter().map(|lag| format!("spatial_corr_{}", lag)));
+        cols.push("temporal_corr".to_string());
+        cols.push("centre_density".to_string());
+        cols.join(",")
+    }
+}
+
+fn format_optional(value: Option<f64>, missing: &str) -> String {
+    match value {
+        Some(v) => format!("{}", v),
+        None => missing.to_string(),
+    }
+}
+
+impl GenerationStats {
+    /// Format as a CSV row. Undefined correlations are left empty.
+    pub fn to_csv(&self) -> String {
+        let mut cols = vec![self.generation.to_string(), self.density.to_string()];
+        cols.extend(self.block_entropy.iter().map(|(_, h)| h.to_string()));
+        cols.extend(
+            self.spatial_correlation
+                .iter()
+                .map(|&(_, c)| format_optional(c, "")),
+        );
+        cols.push(format_optional(self.temporal_correlation, ""));
+        cols.push(self.centre_density.to_string());
+        cols.join(",")
+    }
+
+    /// Format as a JSON object. Undefined correlations are `null`.
+    pub fn to_json(&self) -> String {
+        let entropy: Vec<String> = self
+            .block_entropy
+            .iter()
+            .map(|(k, h)| format!("\"{}\":{}", k, h))
+            .collect();
+        let spatial: Vec<String> = self
+            .spatial_correlation
+            .iter()
+            .map(|&(lag, c)| format!("\"{}\":{}", lag, format_optional(c, "null")))
+            .collect();
+        format!(
+            "{{\"generation\":{},\"density\":{},\"block_entropy\":{{{}}},\"spatial_correlation\":{{{}}},\"temporal_correlation\":{},\"centre_density\":{}}}",
+            self.generation,
+            self.density,
+            entropy.join(","),
+            spatial.join(","),
+            format_optional(self.temporal_correlation, "null"),
+            self.centre_density
+        )
+    }
+}
+
+/// Draw the collected statistics as a line chart and save it as a PNG.
+///
+/// The vertical axis spans -1 to 1 with a grey zero line. Series colours:
+/// density (red), centre column density (blue), block entropy normalised by
+/// block size (greens), spatial correlation (oranges), temporal correlation (purple).
+#[cfg(feature = "png")]
+pub fn save_chart(records: &[GenerationStats], output_path: &str, width: u32, height: u32) {
+    let mut buffer = vec![255u8; (width * height * 4) as usize];
+    let (w, h) = (width as i32, height as i32);
+    let to_y = |v: f64| ((1.0 - (v.clamp(-1.0, 1.0) + 1.0) / 2.0) * (h - 1) as f64).round() as i32;
+    let to_x = |i: usize| {
+        if records.len() > 1 {
+            (i as f64 / (records.len() - 1) as f64 * (w - 1) as f64).round() as i32
+        } else {
+            0
+        }
+    };
+    draw_line_bresenham_rgba(
+        &mut buffer,
+        w,
+        h,
+        0,
+        to_y(0.0),
+        w - 1,
+        to_y(0.0),
+        Rgb([200, 200, 200]),
+        1,
+    );
+
+    let mut series: Vec<(Rgb<u8>, Vec<Option<f64>>)> = Vec::new();
+    series.push((
+        Rgb([220, 40, 40]),
+        records.iter().map(|r| Some(r.density)).collect(),
+    ));
+    series.push((
+        Rgb([40, 80, 220]),
+        records.iter().map(|r| Some(r.centre_density)).collect(),
+    ));
+    let entropy_count = records.first().map_or(0, |r| r.block_entropy.len());
+    for k in 0..entropy_count {
+        let shade = (60 + 140 * k / entropy_count) as u8;
+        series.push((
+            Rgb([0, shade, 60]),
+            records
+                .iter()
+                .map(|r| {
+                    let (block_size, h) = r.block_entropy[k];
+                    Some(h / block_size as f64)
+                })
+                .collect(),
+        ));
+    }
+    let lag_count = records.first().map_or(0, |r| r.spatial_correlation.len());
+    for l in 0..lag_count {
+        let shade = (100 + 120 * l / lag_count) as u8;
+        series.push((
+            Rgb([240, shade, 0]),
+            records.iter().map(|r| r.spatial_correlation[l].1).collect(),
+        ));
+    }
+    series.push((
+        Rgb([140, 40, 180]),
+        records.iter().map(|r| r.temporal_correlation).collect(),
+    ));
+
+    for (color, values) in &series {
+        let mut last: Option<(i32, i32)> = None;
+        for (i, value) in values.iter().enumerate() {
+            match value {
+                Some(v) => {
+                    let point = (to_x(i), to_y(*v));
+                    let (x0, y0) = last.unwrap_or(point);
+                    draw_line_bresenham_rgba(
+                        &mut buffer,
+                        w,
+                        h,
+                        x0,
+                        y0,
+                        point.0,
+                        point.1,
+                        *color,
+                        1,
+                    );
+                    last = Some(point);
+                }
+                None => last = None,
+            }
+        }
+    }
+
+    let img: ImageBuffer<Rgba<u8>, _> =
+        ImageBuffer::from_raw(width, height, buffer).expect("Failed to create image buffer");
+    img.save(output_path).expect("Failed to save PNG");
+}
@@ -0,0 +1,58 @@
+use alloc::{vec, vec::Vec};
+
+/// Left-right reflection of a rule: the output for `(l, c, r)` becomes the output for `(r, c, l)`.
+pub fn mirror(rule: u8) -> u8 {
+    let mut mirrored = 0u8;
+    for idx in 0..8u8 {
+        let (l, c, r) = ((idx >> 2) & 1, (idx >> 1) & 1, idx & 1);
+        let reflected = (r << 2) | (c << 1) | l;
+        mirrored |= ((rule >> reflected) & 1) << idx;
+    }
+    mirrored
+}
+
+/// 0/1 complement of a rule: swap the roles of alive and dead cells.
+pub fn complement(rule: u8) -> u8 {
+    let mut complemented = 0u8;
+    for idx in 0..8u8 {
+        let out = 1 - ((rule >> (7 - idx)) & 1);
+        complemented |= out << idx;
+    }
+    complemented
+}
+
+/// Reflection combined with complement.
+pub fn mirror_complement(rule: u8) -> u8 {
+    complement(mirror(rule))
+}
+
+/// All distinct rules equivalent to `rule`, sorted ascending.
+pub fn equivalents(rule: u8) -> Vec<u8> {
+    let mut rules = vec![
+        rule,
+        mirror(rule),
+        complement(rule),
+        mirror_complement(rule),
+    ];
+    rules.sort_unstable();
+    rules.dedup();
+    rules
+}
+
+/// Smallest rule number in the equivalence class of `rule`.
+pub fn canonical(rule: u8) -> u8 {
+    equivalents(rule)[0]
+}
+
+/// Whether `rule` is the canonical representative of its equivalence class.
+pub fn is_canonical(rule: u8) -> bool {
+    canonical(rule) == rule
+}
+
+/// The 88 equivalence classes of elementary rules, ordered by canonical rule.
+pub fn equivalence_classes() -> Vec<Vec<u8>> {
+    (0..=255u8)
+        .filter(|&rule| is_canonical(rule))
+        .map(equivalents)
+        .collect()
+}
@@ -0,0 +1,178 @@
+use ca::image_output::{gradient_t, lerp_color};
+use image::Rgb;
+use std::io::{self, Write};
+
+/// Dead from/to and alive from/to gradient colours.
+pub type Gradients = (Rgb<u8>, Rgb<u8>, Rgb<u8>, Rgb<u8>);
+
+/// How cells are packed into terminal characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalMode {
+    /// One `█` or space per cell.
+    Block,
+    /// `▀` half blocks, two generations per line.
+    Half,
+    /// Braille patterns, 2 cells by 4 generations per character.
+    Braille,
+}
+
+impl TerminalMode {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "block" => TerminalMode::Block,
+            "half" => TerminalMode::Half,
+            "braille" => TerminalMode::Braille,
+            _ => panic!("Terminal mode must be 'block', 'half' or 'braille'"),
+        }
+    }
+
+    /// Cells across one character.
+    pub fn cells_per_char(self) -> usize {
+        match self {
+            TerminalMode::Braille => 2,
+            _ => 1,
+        }
+    }
+
+    /// Generations down one line.
+    pub fn rows_per_line(self) -> usize {
+        match self {
+            TerminalMode::Block => 1,
+            TerminalMode::Half => 2,
+            TerminalMode::Braille => 4,
+        }
+    }
+}
+
+/// Width of the terminal in characters, falling back to `$COLUMNS` and then 80.
+pub fn terminal_columns() -> usize {
+    if let Some((terminal_size::Width(w), _)) = terminal_size::terminal_size() {
+        return w as usize;
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .unwrap_or(80)
+}
+
+/// Renders generations as lines of terminal text, optionally in 24-bit colour.
+pub struct TerminalRenderer {
+    mode: TerminalMode,
+    /// Gradient colours, or `None` for plain text.
+    colors: Option<Gradients>,
+    width: usize,
+    /// Generations over which the colour gradient runs (it repeats after that).
+    height: usize,
+}
+
+impl TerminalRenderer {
+    pub fn new(mode: TerminalMode, colors: Option<Gradients>, width: usize, height: usize) -> Self {
+        TerminalRenderer {
+            mode,
+            colors,
+            width,
+            height,
+        }
+    }
+
+    pub fn rows_per_line(&self) -> usize {
+        self.mode.rows_per_line()
+    }
+
+    /// Colour of cell `x` in generation `y`, following the same gradients as the PNG output.
+    fn color(&self, cell: u8, x: usize, y: usize) -> Rgb<u8> {
+        let (dead_from, dead_to, alive_from, alive_to) = self.colors.expect("Colours are set");
+        let t = gradient_t(x, y % self.height.max(1), self.width, self.height);
+        if cell == 1 {
+            lerp_color(&alive_from, &alive_to, t)
+        } else {
+            lerp_color(&dead_from, &dead_to, t)
+        }
+    }
+
+    /// Render one line of output from up to `rows_per_line` generations, the first of
+    /// which is generation `y`. Missing generations are drawn as dead cells.
+    pub fn render_line(&self, rows: &[&[u8]], y: usize) -> String {
+        let cell = |dy: usize, x: usize| -> u8 {
+            rows.get(dy)
+                .and_then(|row| row.get(x))
+                .copied()
+                .unwrap_or(0)
+        };
+        let mut line = String::new();
+        let chars = self.width.div_ceil(self.mode.cells_per_char());
+        for cx in 0..chars {
+            match self.mode {
+                TerminalMode::Block => {
+                    let c = cell(0, cx);
+                    if self.colors.is_some() {
+                        push_fg(&mut line, self.color(c, cx, y));
+                        line.push('█');
+                    } else {
+                        line.push(if c == 1 { '█' } else { ' ' });
+                    }
+                }
+                TerminalMode::Half => {
+                    let (top, bottom) = (cell(0, cx), cell(1, cx));
+                    if self.colors.is_some() {
+                        push_fg(&mut line, self.color(top, cx, y));
+                        push_bg(&mut line, self.color(bottom, cx, y + 1));
+                        line.push('▀');
+                    } else {
+                        line.push(match (top, bottom) {
+                            (0, 0) => ' ',
+                            (1, 0) => '▀',
+                            (0, _) => '▄',
+                            _ => '█',
+                        });
+                    }
+                }
+                TerminalMode::Braille => {
+                    // Dot bits for (column, row) within the 2x4 glyph, per the Unicode braille layout
+                    const DOTS: [[u32; 4]; 2] =
+                        [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+                    let mut bits = 0;
+                    for (dx, column) in DOTS.iter().enumerate() {
+                        for (dy, bit) in column.iter().enumerate() {
+                            if cell(dy, cx * 2 + dx) == 1 {
+                                bits |= bit;
+                            }
+                        }
+                    }
+                    if self.colors.is_some() {
+                        push_fg(&mut line, self.color(1, cx * 2, y));
+                        push_bg(&mut line, self.color(0, cx * 2, y));
+                    }
+                    line.push(char::from_u32(0x2800 + bits).expect("Valid braille pattern"));
+                }
+            }
+        }
+        if self.colors.is_some() {
+            line.push_str("\x1b[0m");
+        }
+        line
+    }
+
+    /// Render all generations to `out`.
+    pub fn write_all(&self, out: &mut impl Write, generations: &[Vec<u8>]) -> io::Result<()> {
+        for (i, chunk) in generations.chunks(self.rows_per_line()).enumerate() {
+            let rows: Vec<&[u8]> = chunk.iter().map(|row| row.as_slice()).collect();
+            writeln!(out, "{}", self.render_line(&rows, i * self.rows_per_line()))?;
+        }
+        Ok(())
+    }
+}
+
+fn push_fg(line: &mut String, color: Rgb<u8>) {
+    line.push_str(&format!(
+        "\x1b[38;2;{};{};{}m",
+        color[0], color[1], color[2]
+    ));
+}
+
+fn push_bg(line: &mut String, color: Rgb<u8>) {
+    line.push_str(&format!(
+        "\x1b[48;2;{};{};{}m",
+        color[0], color[1], color[2]
+    ));
+}
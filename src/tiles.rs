@@ -0,0 +1,210 @@
+//! Tiles of a space-time diagram at any zoom, for panning through runs too large to draw
+//! as one image.
+
+use crate::image_output::{gradient_t, lerp_color};
+use crate::Automaton;
+use image::Rgb;
+
+/// Renders rectangular tiles of a run of `generations` generations. Generations are
+/// simulated only as far as a tile has needed and cached, so panning back is free.
+pub(crate) struct TileRenderer {
+    automaton: Automaton,
+    width: usize,
+    generations: usize,
+    cells: Vec<u8>,
+    dead_from: Rgb<u8>,
+    dead_to: Rgb<u8>,
+    alive_from: Rgb<u8>,
+    alive_to: Rgb<u8>,
+}
+
+/// Cells under one zoomed-out pixel: counts and coordinate sums per state, enough to
+/// average their gradient colours exactly.
+#[derive(Clone, Copy, Default)]
+struct CellSums {
+    count: [u64; 2],
+    sum_x: [u64; 2],
+    sum_y: [u64; 2],
+}
+
+impl TileRenderer {
+    pub(crate) fn new(
+        automaton: Automaton,
+        generations: usize,
+        dead_from: Rgb<u8>,
+        dead_to: Rgb<u8>,
+        alive_from: Rgb<u8>,
+        alive_to: Rgb<u8>,
+    ) -> Self {
+        assert!(generations > 0, "A run needs at least one generation");
+        TileRenderer {
+            width: automaton.row().len(),
+            cells: automaton.row().to_vec(),
+            automaton,
+            generations,
+            dead_from,
+            dead_to,
+            alive_from,
+            alive_to,
+        }
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.width
+    }
+
+    pub(crate) fn generations(&self) -> usize {
+        self.generations
+    }
+
+    /// Number of generations simulated and cached so far.
+    pub(crate) fn simulated_rows(&self) -> usize {
+        self.cells.len() / self.width
+    }
+
+    fn simulate_to(&mut self, rows: usize) {
+        while self.simulated_rows() < rows.min(self.generations) {
+            self.automaton.step();
+            self.cells.extend_from_slice(self.automaton.row());
+        }
+    }
+
+    /// RGBA pixels of the `tile_width` x `tile_height` tile whose top left pixel is
+    /// `(x, y)` in the diagram drawn at `zoom`: a positive zoom draws each cell as a
+    /// `zoom` x `zoom` square, a negative one averages `-zoom` x `-zoom` cells into each
+    /// pixel. Pixels outside the diagram are transparent.
+    pub(crate) fn tile(
+        &mut self,
+        x: i64,
+        y: i64,
+        tile_width: usize,
+        tile_height: usize,
+        zoom: i32,
+    ) -> Vec<u8> {
+        assert!(zoom != 0, "Zoom must not be 0");
+        let mut pixels = vec![0u8; tile_width * tile_height * 4];
+        if zoom >= -1 {
+            self.draw_zoomed_in(
+                &mut pixels,
+                x,
+                y,
+                tile_width,
+                tile_height,
+                zoom.max(1) as i64,
+            );
+        } else {
+            self.draw_zoomed_out(&mut pixels, x, y, tile_width, -zoom as i64);
+        }
+        pixels
+    }
+
+    fn draw_zoomed_in(
+        &mut self,
+        pixels: &mut [u8],
+        x: i64,
+        y: i64,
+        tile_width: usize,
+        tile_height: usize,
+        scale: i64,
+    ) {
+        let last_row = (y + tile_height as i64 - 1).div_euclid(scale) + 1;
+        self.simulate_to(last_row.max(0) as usize);
+        for (j, line) in pixels.chunks_mut(tile_width * 4).enumerate() {
+            let cy = (y + j as i64).div_euclid(scale);
+            if cy < 0 || cy as usize >= self.simulated_rows() {
+                continue;
+            }
+            let cy = cy as usize;
+            for (i, pixel) in line.chunks_mut(4).enumerate() {
+                let cx = (x + i as i64).div_euclid(scale);
+                if cx < 0 || cx as usize >= self.width {
+                    continue;
+                }
+                let cx = cx as usize;
+                let t = gradient_t(cx, cy, self.width, self.generations);
+                let color = if self.cells[cy * self.width + cx] == 1 {
+                    lerp_color(&self.alive_from, &self.alive_to, t)
+                } else {
+                    lerp_color(&self.dead_from, &self.dead_to, t)
+                };
+                pixel.copy_from_slice(&[color[0], color[1], color[2], 255]);
+            }
+        }
+    }
+
+    fn draw_zoomed_out(
+        &mut self,
+        pixels: &mut [u8],
+        x: i64,
+        y: i64,
+        tile_width: usize,
+        cells_per_pixel: i64,
+    ) {
+        let n = cells_per_pixel;
+        let width = self.width as i64;
+        let mut sums = vec![CellSums::default(); tile_width];
+        for (j, line) in pixels.chunks_mut(tile_width * 4).enumerate() {
+            let top = (y + j as i64) * n;
+            let rows = top.max(0)..(top + n).min(self.generations as i64);
+            if rows.is_empty() {
+                continue;
+            }
+            self.simulate_to(rows.end as usize);
+            sums.fill(CellSums::default());
+            for cy in rows {
+                let row = &self.cells[cy as usize * self.width..][..self.width];
+                for (i, sum) in sums.iter_mut().enumerate() {
+                    let left = (x + i as i64) * n;
+                    for cx in left.max(0)..(left + n).min(width) {
+                        let state = row[cx as usize].min(1) as usize;
+                        sum.count[state] += 1;
+                        sum.sum_x[state] += cx as u64;
+                        sum.sum_y[state] += cy as u64;
+                    }
+                }
+            }
+            for (pixel, sum) in line.chunks_mut(4).zip(&sums) {
+                let total = sum.count[0] + sum.count[1];
+                if total == 0 {
+                    continue;
+                }
+                let mut color = [0f64; 3];
+                for (state, from, to) in [
+                    (0, self.dead_from, self.dead_to),
+                    (1, self.alive_from, self.alive_to),
+                ] {
+                    // The gradient is linear in x and y, so the mean colour follows
+                    // from the mean coordinates.
+                    let t_sum =
+                        self.gradient_sum(sum.count[state], sum.sum_x[state], sum.sum_y[state]);
+                    for (c, channel) in color.iter_mut().enumerate() {
+                        let (from, to) = (from[c] as f64, to[c] as f64);
+                        *channel += from * sum.count[state] as f64 + (to - from) * t_sum;
+                    }
+                }
+                for (c, channel) in color.iter().enumerate() {
+                    pixel[c] = (channel / total as f64).round() as u8;
+                }
+                pixel[3] = (255.0 * total as f64 / (n * n) as f64).round() as u8;
+            }
+        }
+    }
+
+    /// Sum of `gradient_t` over `count` cells with the given coordinate sums.
+    fn gradient_sum(&self, count: u64, sum_x: u64, sum_y: u64) -> f64 {
+        if count == 0 {
+            return 0.0;
+        }
+        let fx = if self.width > 1 {
+            sum_x as f64 / (self.width - 1) as f64
+        } else {
+            0.0
+        };
+        let fy = if self.generations > 1 {
+            sum_y as f64 / (self.generations - 1) as f64
+        } else {
+            0.0
+        };
+        (fx + fy) / 2.0
+    }
+}
@@ -0,0 +1,1149 @@
+//! Bindings for the web UI, exported to JS with `wasm-bindgen`.
+
+use crate::{
+    classify, damage, image_output, permalink, rule_table, tiles, Automaton, Boundary, RuleFamily,
+    SeedScheme,
+};
+use image::Rgb;
+use image_output::{format_hex_color, try_parse_hex_color, CellShape};
+use wasm_bindgen::prelude::*;
+
+/// WASM-exported (as `run_automaton`): `crate::run_automaton`, throwing for an unknown
+/// seed scheme.
+#[wasm_bindgen(js_name = run_automaton)]
+pub fn wasm_run_automaton(
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+) -> Result<Vec<u8>, JsValue> {
+    wasm_seed_scheme(seed_scheme)?;
+    Ok(crate::run_automaton(
+        rule,
+        random_distribution,
+        width,
+        generations,
+        seed,
+        seed_scheme,
+    ))
+}
+
+/// Seed scheme version from JS, defaulting to the latest when not given.
+fn wasm_seed_scheme(version: Option<u8>) -> Result<SeedScheme, JsValue> {
+    version.map_or(Ok(SeedScheme::LATEST), |v| {
+        SeedScheme::from_version(v)
+            .ok_or_else(|| JsValue::from_str("Seed scheme must be 1, 2 or 3"))
+    })
+}
+
+fn wasm_family(name: &str) -> Result<RuleFamily, JsValue> {
+    RuleFamily::from_name(name)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown rule family: {}", name)))
+}
+
+fn wasm_boundary(name: &str) -> Result<Boundary, JsValue> {
+    Boundary::from_name(name)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown boundary: {}", name)))
+}
+
+fn wasm_shape(name: &str) -> Result<CellShape, JsValue> {
+    CellShape::from_name(name)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown cell shape: {}", name)))
+}
+
+fn wasm_color(s: &str) -> Result<Rgb<u8>, JsValue> {
+    try_parse_hex_color(s).ok_or_else(|| JsValue::from_str("Color must be in format #RRGGBB"))
+}
+
+/// WASM-exported: Generate RGBA buffer for automaton image (for canvas rendering)
+#[wasm_bindgen]
+pub fn generate_automaton_image(
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    rule_family: &str,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    bg_from: &str,
+    bg_to: &str,
+    fg_from: &str,
+    fg_to: &str,
+) -> Result<Vec<u8>, JsValue> {
+    let automaton = Automaton::new_with_scheme(
+        rule,
+        random_distribution,
+        width,
+        seed,
+        wasm_seed_scheme(seed_scheme)?,
+    )
+    .with_family(wasm_family(rule_family)?);
+    automaton_image(
+        automaton,
+        generations,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        bg_from,
+        bg_to,
+        fg_from,
+        fg_to,
+    )
+}
+
+/// WASM-exported: Generate RGBA buffer for an automaton image starting from an explicit
+/// initial row (one byte per cell, 0 or 1). The seed only affects stochastic updates.
+#[wasm_bindgen]
+pub fn generate_image_from_row(
+    rule: u8,
+    initial_row: &[u8],
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    rule_family: &str,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    bg_from: &str,
+    bg_to: &str,
+    fg_from: &str,
+    fg_to: &str,
+) -> Result<Vec<u8>, JsValue> {
+    let automaton = Automaton::from_row(rule, initial_row.iter().map(|&c| c.min(1)).collect())
+        .with_seed_scheme(seed, wasm_seed_scheme(seed_scheme)?)
+        .with_family(wasm_family(rule_family)?);
+    automaton_image(
+        automaton,
+        generations,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        bg_from,
+        bg_to,
+        fg_from,
+        fg_to,
+    )
+}
+
+/// The first `generations` rows of `automaton`, starting with its current row.
+fn automaton_generations(automaton: &mut Automaton, generations: usize) -> Vec<Vec<u8>> {
+    let mut generations_vec = Vec::with_capacity(generations);
+    generations_vec.push(automaton.row().to_vec());
+    for _ in 1..generations {
+        automaton.step();
+        generations_vec.push(automaton.row().to_vec());
+    }
+    generations_vec
+}
+
+/// Run `automaton` for `generations` rows and draw them into an RGBA buffer.
+fn automaton_image(
+    mut automaton: Automaton,
+    generations: usize,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    bg_from: &str,
+    bg_to: &str,
+    fg_from: &str,
+    fg_to: &str,
+) -> Result<Vec<u8>, JsValue> {
+    // The renderer falls back to squares for unknown shapes, so check them here
+    wasm_shape(alive_shape)?;
+    wasm_shape(dead_shape)?;
+    let dead_from = wasm_color(bg_from)?;
+    let dead_to = wasm_color(bg_to)?;
+    let alive_from = wasm_color(fg_from)?;
+    let alive_to = wasm_color(fg_to)?;
+    let width = automaton.row().len();
+    let generations_vec = automaton_generations(&mut automaton, generations);
+    Ok(image_output::generations_to_rgba_buffer(
+        &generations_vec,
+        width,
+        generations,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        dead_from,
+        dead_to,
+        alive_from,
+        alive_to,
+    ))
+}
+
+/// WASM-exported: Renders the same image as `generate_automaton_image` a few generations
+/// at a time, so callers (e.g. a Web Worker) can report progress and show partial results.
+///
+/// The renderer owns its pixel buffer, which JS can read in place through `ptr` and
+/// `byte_len`, and keeps it across `configure` calls.
+#[wasm_bindgen]
+pub struct ImageRenderer {
+    automaton: Automaton,
+    renderer: image_output::RgbaRenderer,
+    generations: usize,
+    width: usize,
+    scale: usize,
+    seed: Option<u64>,
+    seed_scheme: SeedScheme,
+    family: RuleFamily,
+}
+
+impl ImageRenderer {
+    /// Set up a renderer that draws into `buffer`, resizing it as needed.
+    fn build(
+        buffer: Vec<u8>,
+        rule: u8,
+        random_distribution: Option<f64>,
+        width: usize,
+        generations: usize,
+        seed: Option<u64>,
+        seed_scheme: Option<u8>,
+        rule_family: &str,
+        scale: usize,
+        alive_shape: &str,
+        dead_shape: &str,
+        use_links: bool,
+        bg_from: &str,
+        bg_to: &str,
+        fg_from: &str,
+        fg_to: &str,
+    ) -> Result<ImageRenderer, JsValue> {
+        let seed_scheme = wasm_seed_scheme(seed_scheme)?;
+        let family = wasm_family(rule_family)?;
+        let automaton =
+            Automaton::new_with_scheme(rule, random_distribution, width, seed, seed_scheme)
+                .with_family(family);
+        let renderer = image_output::RgbaRenderer::new(
+            buffer,
+            width,
+            generations,
+            scale,
+            wasm_shape(alive_shape)?,
+            wasm_shape(dead_shape)?,
+            use_links,
+            wasm_color(bg_from)?,
+            wasm_color(bg_to)?,
+            wasm_color(fg_from)?,
+            wasm_color(fg_to)?,
+        );
+        Ok(ImageRenderer {
+            automaton,
+            renderer,
+            generations,
+            width,
+            scale,
+            seed,
+            seed_scheme,
+            family,
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl ImageRenderer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        rule: u8,
+        random_distribution: Option<f64>,
+        width: usize,
+        generations: usize,
+        seed: Option<u64>,
+        seed_scheme: Option<u8>,
+        rule_family: &str,
+        scale: usize,
+        alive_shape: &str,
+        dead_shape: &str,
+        use_links: bool,
+        bg_from: &str,
+        bg_to: &str,
+        fg_from: &str,
+        fg_to: &str,
+    ) -> Result<ImageRenderer, JsValue> {
+        Self::build(
+            Vec::new(),
+            rule,
+            random_distribution,
+            width,
+            generations,
+            seed,
+            seed_scheme,
+            rule_family,
+            scale,
+            alive_shape,
+            dead_shape,
+            use_links,
+            bg_from,
+            bg_to,
+            fg_from,
+            fg_to,
+        )
+    }
+
+    /// Start over with new parameters, reusing the pixel buffer when it is big enough.
+    /// Views from `ptr` must be recreated afterwards.
+    pub fn configure(
+        &mut self,
+        rule: u8,
+        random_distribution: Option<f64>,
+        width: usize,
+        generations: usize,
+        seed: Option<u64>,
+        seed_scheme: Option<u8>,
+        rule_family: &str,
+        scale: usize,
+        alive_shape: &str,
+        dead_shape: &str,
+        use_links: bool,
+        bg_from: &str,
+        bg_to: &str,
+        fg_from: &str,
+        fg_to: &str,
+    ) -> Result<(), JsValue> {
+        let buffer = self.renderer.take_buffer();
+        *self = Self::build(
+            buffer,
+            rule,
+            random_distribution,
+            width,
+            generations,
+            seed,
+            seed_scheme,
+            rule_family,
+            scale,
+            alive_shape,
+            dead_shape,
+            use_links,
+            bg_from,
+            bg_to,
+            fg_from,
+            fg_to,
+        )?;
+        Ok(())
+    }
+
+    /// Start from `row` (one byte per cell, 0 or 1) instead of the configured initial row,
+    /// as `generate_image_from_row` does. Call this before rendering any generations.
+    pub fn set_initial_row(&mut self, row: &[u8]) -> Result<(), JsValue> {
+        if self.renderer.rows() != 0 {
+            return Err(JsValue::from_str("Set the initial row before rendering"));
+        }
+        if row.len() != self.width {
+            return Err(JsValue::from_str("Initial row must match the width"));
+        }
+        let row = row.iter().map(|&c| c.min(1)).collect();
+        self.automaton = Automaton::from_row(self.automaton.rule(), row)
+            .with_seed_scheme(self.seed, self.seed_scheme)
+            .with_family(self.family);
+        Ok(())
+    }
+
+    /// Simulate and draw up to `n` more generations, returning how many are now drawn.
+    pub fn render_rows(&mut self, n: usize) -> usize {
+        for _ in 0..n {
+            if self.is_done() {
+                break;
+            }
+            if self.renderer.rows() > 0 {
+                self.automaton.step();
+            }
+            self.renderer.push_row(self.automaton.row());
+        }
+        self.renderer.rows()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.renderer.rows() >= self.generations
+    }
+
+    /// Generations whose pixels will not change any more (with links, the last one drawn
+    /// is finished only once the next is drawn).
+    pub fn finished_rows(&self) -> usize {
+        self.renderer.finished_rows()
+    }
+
+    /// Image width in pixels.
+    pub fn pixel_width(&self) -> usize {
+        self.width * self.scale
+    }
+
+    /// Image height in pixels.
+    pub fn pixel_height(&self) -> usize {
+        self.generations * self.scale
+    }
+
+    /// RGBA pixels of generations `start..end`, for drawing a band with `putImageData`.
+    pub fn band(&self, start: usize, end: usize) -> Vec<u8> {
+        let row_bytes = self.pixel_width() * self.scale * 4;
+        let end = end.min(self.generations);
+        self.renderer.buffer()[start.min(end) * row_bytes..end * row_bytes].to_vec()
+    }
+
+    /// RGBA pixels of the whole image drawn so far.
+    pub fn image(&self) -> Vec<u8> {
+        self.renderer.buffer().to_vec()
+    }
+
+    /// Address of the RGBA pixel buffer in WASM memory, for building an `ImageData` over
+    /// it without copying. The buffer stays at this address until `configure` is called,
+    /// but JS views must be recreated whenever WASM memory grows.
+    pub fn ptr(&self) -> *const u8 {
+        self.renderer.buffer().as_ptr()
+    }
+
+    /// Length of the RGBA pixel buffer in bytes.
+    pub fn byte_len(&self) -> usize {
+        self.renderer.buffer().len()
+    }
+}
+
+/// WASM-exported: Parse a rule number, transition string (`111:0 110:1 ...`) or Golly table
+#[wasm_bindgen]
+pub fn rule_from_table(input: &str) -> Result<u8, JsValue> {
+    rule_table::parse_rule(input).map_err(|e| JsValue::from_str(&e))
+}
+
+/// WASM-exported: Encode the web UI's settings as a permalink code for the URL hash
+#[wasm_bindgen]
+pub fn encode_permalink(
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    rule_family: &str,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    bg_from: &str,
+    bg_to: &str,
+    fg_from: &str,
+    fg_to: &str,
+    initial_row: Option<Vec<u8>>,
+) -> Result<String, JsValue> {
+    wasm_config(
+        rule,
+        random_distribution,
+        width,
+        generations,
+        seed,
+        seed_scheme,
+        rule_family,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        bg_from,
+        bg_to,
+        fg_from,
+        fg_to,
+        initial_row,
+    )?
+    .encode()
+    .map_err(|e| JsValue::from_str(&e))
+}
+
+/// The web UI's settings as a permalink config.
+fn wasm_config(
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    rule_family: &str,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    bg_from: &str,
+    bg_to: &str,
+    fg_from: &str,
+    fg_to: &str,
+    initial_row: Option<Vec<u8>>,
+) -> Result<permalink::Config, JsValue> {
+    wasm_shape(alive_shape)?;
+    wasm_shape(dead_shape)?;
+    Ok(permalink::Config {
+        rule,
+        random_distribution,
+        width,
+        generations,
+        seed,
+        seed_scheme: wasm_seed_scheme(seed_scheme)?,
+        family: wasm_family(rule_family)?,
+        boundary: Boundary::Dead,
+        initial_row,
+        scale,
+        alive_shape: alive_shape.to_string(),
+        dead_shape: dead_shape.to_string(),
+        links: use_links,
+        dead_from: wasm_color(bg_from)?,
+        dead_to: wasm_color(bg_to)?,
+        alive_from: wasm_color(fg_from)?,
+        alive_to: wasm_color(fg_to)?,
+    })
+}
+
+/// WASM-exported: A decoded permalink, with its settings in the web UI's terms
+#[wasm_bindgen]
+pub struct Permalink {
+    config: permalink::Config,
+}
+
+#[wasm_bindgen]
+impl Permalink {
+    /// Decode a permalink code, or a URL whose hash is one.
+    pub fn decode(code: &str) -> Result<Permalink, JsValue> {
+        permalink::Config::decode(code)
+            .map(|config| Permalink { config })
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn rule(&self) -> u8 {
+        self.config.rule
+    }
+
+    pub fn random_distribution(&self) -> Option<f64> {
+        self.config.random_distribution
+    }
+
+    pub fn width(&self) -> usize {
+        self.config.width
+    }
+
+    pub fn generations(&self) -> usize {
+        self.config.generations
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.config.seed
+    }
+
+    pub fn seed_scheme(&self) -> u8 {
+        self.config.seed_scheme.version()
+    }
+
+    pub fn rule_family(&self) -> String {
+        self.config.family.name().to_string()
+    }
+
+    pub fn boundary(&self) -> String {
+        self.config.boundary.name().to_string()
+    }
+
+    pub fn initial_row(&self) -> Option<Vec<u8>> {
+        self.config.initial_row.clone()
+    }
+
+    pub fn scale(&self) -> usize {
+        self.config.scale
+    }
+
+    pub fn alive_shape(&self) -> String {
+        self.config.alive_shape.clone()
+    }
+
+    pub fn dead_shape(&self) -> String {
+        self.config.dead_shape.clone()
+    }
+
+    pub fn links(&self) -> bool {
+        self.config.links
+    }
+
+    pub fn bg_from(&self) -> String {
+        format_hex_color(self.config.dead_from)
+    }
+
+    pub fn bg_to(&self) -> String {
+        format_hex_color(self.config.dead_to)
+    }
+
+    pub fn fg_from(&self) -> String {
+        format_hex_color(self.config.alive_from)
+    }
+
+    pub fn fg_to(&self) -> String {
+        format_hex_color(self.config.alive_to)
+    }
+}
+
+/// WASM-exported: Estimate the Wolfram class (1-4) of a rule with the default classifier settings
+#[wasm_bindgen]
+pub fn estimate_wolfram_class(rule: u8) -> u8 {
+    classify::estimate_class(rule, 201, 1024, 4, 0)
+        .expect("The default settings are valid")
+        .0 as u8
+}
+
+/// WASM-exported: Generate RGBA buffer with the damage from flipping one initial cell
+/// blended over the normal image in `damage_color`
+#[wasm_bindgen]
+pub fn generate_damage_image(
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    rule_family: &str,
+    boundary: &str,
+    flip_index: usize,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    bg_from: &str,
+    bg_to: &str,
+    fg_from: &str,
+    fg_to: &str,
+    damage_color: &str,
+    initial_row: Option<Vec<u8>>,
+) -> Result<Vec<u8>, JsValue> {
+    check_flip_index(width, flip_index)?;
+    wasm_shape(alive_shape)?;
+    wasm_shape(dead_shape)?;
+    let automaton = wasm_automaton(
+        rule,
+        random_distribution,
+        width,
+        seed,
+        seed_scheme,
+        wasm_family(rule_family)?,
+        initial_row,
+    )?
+    .with_boundary(wasm_boundary(boundary)?);
+    let original = automaton.clone().run(generations);
+    let diff = damage::damage_spread(&automaton, generations, flip_index);
+    let generations_vec: Vec<Vec<u8>> = original.chunks(width).map(|c| c.to_vec()).collect();
+    let damage_vec: Vec<Vec<u8>> = diff.chunks(width).map(|c| c.to_vec()).collect();
+    let mut buffer = image_output::generations_to_rgba_buffer(
+        &generations_vec,
+        width,
+        generations,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        wasm_color(bg_from)?,
+        wasm_color(bg_to)?,
+        wasm_color(fg_from)?,
+        wasm_color(fg_to)?,
+    );
+    image_output::overlay_cells_rgba(
+        &mut buffer,
+        &damage_vec,
+        width,
+        scale,
+        wasm_color(damage_color)?,
+    );
+    Ok(buffer)
+}
+
+/// WASM-exported: Growth rate of the damage cone (cells per generation) for a flipped initial cell
+#[wasm_bindgen]
+pub fn damage_growth(
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    rule_family: &str,
+    boundary: &str,
+    flip_index: usize,
+    initial_row: Option<Vec<u8>>,
+) -> Result<f64, JsValue> {
+    check_flip_index(width, flip_index)?;
+    let automaton = wasm_automaton(
+        rule,
+        random_distribution,
+        width,
+        seed,
+        seed_scheme,
+        wasm_family(rule_family)?,
+        initial_row,
+    )?
+    .with_boundary(wasm_boundary(boundary)?);
+    let diff = damage::damage_spread(&automaton, generations, flip_index);
+    Ok(damage::damage_growth_rate(&diff, width))
+}
+
+/// The damage functions split runs into rows of `width` cells and flip one of them.
+fn check_flip_index(width: usize, flip_index: usize) -> Result<(), JsValue> {
+    if width == 0 {
+        return Err(JsValue::from_str("Width must be positive"));
+    }
+    if flip_index >= width {
+        return Err(JsValue::from_str("Flipped cell must be inside the row"));
+    }
+    Ok(())
+}
+
+/// Automaton for the WASM functions, starting from `initial_row` when given (where the
+/// seed only drives stochastic updates, as with the CLI's `--init`).
+fn wasm_automaton(
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    family: RuleFamily,
+    initial_row: Option<Vec<u8>>,
+) -> Result<Automaton, JsValue> {
+    let scheme = wasm_seed_scheme(seed_scheme)?;
+    let automaton = match initial_row {
+        Some(row) => {
+            if row.len() != width {
+                return Err(JsValue::from_str("Initial row must match the width"));
+            }
+            Automaton::from_row(rule, row.iter().map(|&c| c.min(1)).collect())
+                .with_seed_scheme(seed, scheme)
+        }
+        None => Automaton::new_with_scheme(rule, random_distribution, width, seed, scheme),
+    };
+    Ok(automaton.with_family(family))
+}
+
+/// WASM-exported (as `Automaton`): A running automaton for animated playback. Each
+/// `step` draws the new generation at the bottom of a window of `rows` generations,
+/// scrolling the older ones up, so a frame costs one row rather than a whole image.
+#[wasm_bindgen(js_name = Automaton)]
+pub struct Playback {
+    automaton: Automaton,
+    renderer: image_output::RgbaRenderer,
+    width: usize,
+    rows: usize,
+    scale: usize,
+}
+
+#[wasm_bindgen(js_class = Automaton)]
+impl Playback {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        rule: u8,
+        random_distribution: Option<f64>,
+        width: usize,
+        rows: usize,
+        seed: Option<u64>,
+        seed_scheme: Option<u8>,
+        rule_family: &str,
+        scale: usize,
+        alive_shape: &str,
+        dead_shape: &str,
+        use_links: bool,
+        bg_from: &str,
+        bg_to: &str,
+        fg_from: &str,
+        fg_to: &str,
+        initial_row: Option<Vec<u8>>,
+    ) -> Result<Playback, JsValue> {
+        if rows == 0 {
+            return Err(JsValue::from_str("Playback needs at least one row"));
+        }
+        let automaton = wasm_automaton(
+            rule,
+            random_distribution,
+            width,
+            seed,
+            seed_scheme,
+            wasm_family(rule_family)?,
+            initial_row,
+        )?;
+        let mut renderer = image_output::RgbaRenderer::new(
+            Vec::new(),
+            width,
+            rows,
+            scale,
+            wasm_shape(alive_shape)?,
+            wasm_shape(dead_shape)?,
+            use_links,
+            wasm_color(bg_from)?,
+            wasm_color(bg_to)?,
+            wasm_color(fg_from)?,
+            wasm_color(fg_to)?,
+        );
+        renderer.scroll_row(automaton.row(), 0);
+        Ok(Playback {
+            automaton,
+            renderer,
+            width,
+            rows,
+            scale,
+        })
+    }
+
+    /// Advance one generation and draw it.
+    pub fn step(&mut self) {
+        self.automaton.step();
+        self.renderer
+            .scroll_row(self.automaton.row(), self.automaton.generation());
+    }
+
+    /// Advance `n` generations, drawing each.
+    pub fn steps(&mut self, n: usize) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+
+    /// Index of the newest generation (0 for the initial row).
+    pub fn generation(&self) -> usize {
+        self.automaton.generation()
+    }
+
+    /// The newest generation's cells.
+    pub fn row(&self) -> Vec<u8> {
+        self.automaton.row().to_vec()
+    }
+
+    /// Address of the RGBA pixel buffer in WASM memory; see `ImageRenderer::ptr`.
+    pub fn ptr(&self) -> *const u8 {
+        self.renderer.buffer().as_ptr()
+    }
+
+    /// Length of the RGBA pixel buffer in bytes.
+    pub fn byte_len(&self) -> usize {
+        self.renderer.buffer().len()
+    }
+
+    /// Window width in pixels.
+    pub fn pixel_width(&self) -> usize {
+        self.width * self.scale
+    }
+
+    /// Window height in pixels.
+    pub fn pixel_height(&self) -> usize {
+        self.rows * self.scale
+    }
+}
+
+/// WASM-exported: Pan and zoom viewer for runs too large to draw whole. Renders any
+/// rectangular tile of the space-time diagram at any zoom, simulating generations only
+/// when a tile first reaches them and caching them for later tiles.
+#[wasm_bindgen]
+pub struct TileViewer {
+    tiles: tiles::TileRenderer,
+}
+
+#[wasm_bindgen]
+impl TileViewer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        rule: u8,
+        random_distribution: Option<f64>,
+        width: usize,
+        generations: usize,
+        seed: Option<u64>,
+        seed_scheme: Option<u8>,
+        rule_family: &str,
+        bg_from: &str,
+        bg_to: &str,
+        fg_from: &str,
+        fg_to: &str,
+        initial_row: Option<Vec<u8>>,
+    ) -> Result<TileViewer, JsValue> {
+        if generations == 0 {
+            return Err(JsValue::from_str("A run needs at least one generation"));
+        }
+        let automaton = wasm_automaton(
+            rule,
+            random_distribution,
+            width,
+            seed,
+            seed_scheme,
+            wasm_family(rule_family)?,
+            initial_row,
+        )?;
+        Ok(TileViewer {
+            tiles: tiles::TileRenderer::new(
+                automaton,
+                generations,
+                wasm_color(bg_from)?,
+                wasm_color(bg_to)?,
+                wasm_color(fg_from)?,
+                wasm_color(fg_to)?,
+            ),
+        })
+    }
+
+    /// RGBA pixels of a `tile_width` x `tile_height` tile with its top left pixel at
+    /// `(x, y)` in the diagram drawn at `zoom`. A positive zoom is pixels per cell; a
+    /// negative one is cells per pixel, averaging their colours. Pixels outside the
+    /// diagram are transparent.
+    pub fn tile(
+        &mut self,
+        x: i32,
+        y: i32,
+        tile_width: usize,
+        tile_height: usize,
+        zoom: i32,
+    ) -> Result<Vec<u8>, JsValue> {
+        if zoom == 0 {
+            return Err(JsValue::from_str("Zoom must not be 0"));
+        }
+        Ok(self
+            .tiles
+            .tile(x as i64, y as i64, tile_width, tile_height, zoom))
+    }
+
+    /// Diagram width in cells.
+    pub fn width(&self) -> usize {
+        self.tiles.width()
+    }
+
+    /// Diagram height in generations.
+    pub fn generations(&self) -> usize {
+        self.tiles.generations()
+    }
+
+    /// Generations simulated so far.
+    pub fn simulated_rows(&self) -> usize {
+        self.tiles.simulated_rows()
+    }
+}
+
+/// An image exported from the web UI's settings, with the data each format needs.
+struct Export {
+    generations_vec: Vec<Vec<u8>>,
+    config: permalink::Config,
+    /// Permalink to embed, unless the image cannot be reproduced from it
+    permalink: Option<String>,
+}
+
+impl Export {
+    fn new(config: permalink::Config) -> Result<Self, JsValue> {
+        // As in the CLI, a random row without a seed has no permalink
+        let unseeded_random = config.initial_row.is_none()
+            && config.seed.is_none()
+            && config.random_distribution.is_some_and(|p| p > 0.0);
+        let permalink = if unseeded_random {
+            None
+        } else {
+            config.encode().ok()
+        };
+        let mut automaton = wasm_automaton(
+            config.rule,
+            config.random_distribution,
+            config.width,
+            config.seed,
+            Some(config.seed_scheme.version()),
+            config.family,
+            config.initial_row.clone(),
+        )?;
+        Ok(Export {
+            generations_vec: automaton_generations(&mut automaton, config.generations),
+            config,
+            permalink,
+        })
+    }
+
+    fn metadata(&self) -> Option<(&str, &str)> {
+        self.permalink
+            .as_deref()
+            .map(|code| (permalink::PNG_KEYWORD, code))
+    }
+}
+
+/// WASM-exported: The image as the CLI saves it to a `.png` file, permalink included,
+/// so the web UI can download it with its parameters.
+#[wasm_bindgen]
+pub fn export_png(
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    rule_family: &str,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    bg_from: &str,
+    bg_to: &str,
+    fg_from: &str,
+    fg_to: &str,
+    initial_row: Option<Vec<u8>>,
+) -> Result<Vec<u8>, JsValue> {
+    let export = Export::new(wasm_config(
+        rule,
+        random_distribution,
+        width,
+        generations,
+        seed,
+        seed_scheme,
+        rule_family,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        bg_from,
+        bg_to,
+        fg_from,
+        fg_to,
+        initial_row,
+    )?)?;
+    let config = &export.config;
+    let buffer = image_output::generations_to_rgba_buffer(
+        &export.generations_vec,
+        width,
+        generations,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        config.dead_from,
+        config.dead_to,
+        config.alive_from,
+        config.alive_to,
+    );
+    Ok(image_output::encode_png(
+        &buffer,
+        (width * scale) as u32,
+        (generations * scale) as u32,
+        export.metadata(),
+    ))
+}
+
+/// WASM-exported: The image as the CLI saves it to a `.svg` file, permalink included.
+#[wasm_bindgen]
+pub fn export_svg(
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    rule_family: &str,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    bg_from: &str,
+    bg_to: &str,
+    fg_from: &str,
+    fg_to: &str,
+    initial_row: Option<Vec<u8>>,
+) -> Result<String, JsValue> {
+    let export = Export::new(wasm_config(
+        rule,
+        random_distribution,
+        width,
+        generations,
+        seed,
+        seed_scheme,
+        rule_family,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        bg_from,
+        bg_to,
+        fg_from,
+        fg_to,
+        initial_row,
+    )?)?;
+    let config = &export.config;
+    let elements = image_output::generations_to_svg_elements(
+        &export.generations_vec,
+        width,
+        generations,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        config.dead_from,
+        config.dead_to,
+        config.alive_from,
+        config.alive_to,
+        0,
+        0,
+    );
+    Ok(image_output::svg_document(
+        &elements,
+        width * scale,
+        generations * scale,
+        export.metadata(),
+    ))
+}
+
+/// WASM-exported: An animation drawing `rows_per_frame` generations per frame, as the
+/// CLI saves it with `--animate`: `format` is "gif" or "apng". The permalink is included.
+#[wasm_bindgen]
+pub fn export_animation(
+    format: &str,
+    rule: u8,
+    random_distribution: Option<f64>,
+    width: usize,
+    generations: usize,
+    seed: Option<u64>,
+    seed_scheme: Option<u8>,
+    rule_family: &str,
+    scale: usize,
+    alive_shape: &str,
+    dead_shape: &str,
+    use_links: bool,
+    bg_from: &str,
+    bg_to: &str,
+    fg_from: &str,
+    fg_to: &str,
+    initial_row: Option<Vec<u8>>,
+    rows_per_frame: usize,
+    frame_delay: u16,
+) -> Result<Vec<u8>, JsValue> {
+    let format = match format {
+        "gif" => image_output::AnimationFormat::Gif,
+        "apng" => image_output::AnimationFormat::Apng,
+        _ => return Err(JsValue::from_str("Animation format must be gif or apng")),
+    };
+    let export = Export::new(wasm_config(
+        rule,
+        random_distribution,
+        width,
+        generations,
+        seed,
+        seed_scheme,
+        rule_family,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        bg_from,
+        bg_to,
+        fg_from,
+        fg_to,
+        initial_row,
+    )?)?;
+    let config = &export.config;
+    Ok(image_output::generations_to_animation(
+        &export.generations_vec,
+        width,
+        generations,
+        scale,
+        alive_shape,
+        dead_shape,
+        use_links,
+        config.dead_from,
+        config.dead_to,
+        config.alive_from,
+        config.alive_to,
+        rows_per_frame,
+        frame_delay,
+        format,
+        export.metadata(),
+    ))
+}
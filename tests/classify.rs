@@ -0,0 +1,38 @@
+//! The classifier must put well-known rules in their textbook Wolfram classes with the
+//! CLI's default settings.
+
+use ca::classify::{estimate_class, WolframClass};
+
+fn class(rule: u8) -> WolframClass {
+    estimate_class(rule, 201, 1024, 4, 0)
+        .expect("The default settings are valid")
+        .0
+}
+
+#[test]
+fn classifies_textbook_rules() {
+    for (rule, expected) in [
+        (0, WolframClass::Uniform),
+        (8, WolframClass::Uniform),
+        (4, WolframClass::Periodic),
+        (184, WolframClass::Periodic),
+        (30, WolframClass::Chaotic),
+        (90, WolframClass::Chaotic),
+        (110, WolframClass::Complex),
+    ] {
+        assert_eq!(class(rule), expected, "rule {}", rule);
+    }
+}
+
+#[test]
+fn uniform_rules_settle_with_period_one() {
+    let (_, metrics) = estimate_class(0, 201, 1024, 4, 0).expect("The settings are valid");
+    assert_eq!(metrics.period, Some(1));
+    assert_eq!(metrics.damage, 0.0);
+}
+
+#[test]
+fn rejects_zero_width_and_no_trials() {
+    assert!(estimate_class(30, 0, 1024, 4, 0).is_err());
+    assert!(estimate_class(30, 201, 1024, 0, 0).is_err());
+}
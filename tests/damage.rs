@@ -0,0 +1,43 @@
+//! Damage spreading must match a second run of the same automaton with the cell flipped
+//! by hand, whatever boundary and stochastic options it uses.
+
+use ca::damage::{damage_growth_rate, damage_spread};
+use ca::{Automaton, Boundary};
+
+/// A periodic rule 30 run with noise from an explicit row, so the seeded RNG is in the
+/// same state however the row is set.
+fn noisy_periodic(row: Vec<u8>) -> Automaton {
+    Automaton::from_row(30, row)
+        .with_boundary(Boundary::Periodic)
+        .with_seed(Some(99))
+        .with_noise(0.05)
+}
+
+#[test]
+fn matches_hand_flipped_periodic_run() {
+    let row: Vec<u8> = (0..24).map(|i| (i % 3 == 0) as u8).collect();
+    let mut flipped = row.clone();
+    flipped[1] ^= 1;
+    let expected: Vec<u8> = noisy_periodic(row.clone())
+        .run(40)
+        .iter()
+        .zip(noisy_periodic(flipped).run(40))
+        .map(|(a, b)| a ^ b)
+        .collect();
+    assert_eq!(damage_spread(&noisy_periodic(row), 40, 1), expected);
+}
+
+#[test]
+fn periodic_damage_wraps_around_the_edges() {
+    let automaton = Automaton::from_row(90, vec![0; 16]).with_boundary(Boundary::Periodic);
+    let diff = damage_spread(&automaton, 3, 0);
+    let second: Vec<usize> = (0..16).filter(|&i| diff[16 + i] == 1).collect();
+    assert_eq!(second, [1, 15]);
+}
+
+#[test]
+fn rule_90_damage_grows_two_cells_per_generation() {
+    let automaton = Automaton::from_row(90, vec![0; 64]);
+    let diff = damage_spread(&automaton, 20, 32);
+    assert_eq!(damage_growth_rate(&diff, 64), 2.0);
+}
@@ -0,0 +1,128 @@
+//! Writing generations in every export format and reading them back, including malformed
+//! input, which must be rejected with an error rather than a panic.
+
+use ca::export::{read_generations, DataFormat, DataWriter};
+use ca::Automaton;
+
+/// A version 1 `.npy` file with the given header text and cell bytes.
+fn npy(header: &str, cells: &[u8]) -> Vec<u8> {
+    let mut data = b"\x93NUMPY\x01\x00".to_vec();
+    data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    data.extend_from_slice(header.as_bytes());
+    data.extend_from_slice(cells);
+    data
+}
+
+#[test]
+fn truncated_npy_headers_are_errors() {
+    let full = npy(
+        "{'descr': '|u1', 'fortran_order': False, 'shape': (2, 2), }",
+        &[0, 1, 1, 0],
+    );
+    for len in [6, 7, 8, 9, 10, 20] {
+        assert!(read_generations(&full[..len]).is_err(), "length {}", len);
+    }
+    assert!(read_generations(b"\x93NUMPY\x02\x00\x01\x00").is_err());
+}
+
+#[test]
+fn truncated_npy_cells_are_errors() {
+    let data = npy(
+        "{'descr': '|u1', 'fortran_order': False, 'shape': (2, 3), }",
+        &[1, 0, 1],
+    );
+    assert!(read_generations(&data).is_err());
+}
+
+#[test]
+fn zero_width_npy_is_an_error() {
+    let data = npy(
+        "{'descr': '|u1', 'fortran_order': False, 'shape': (3, 0), }",
+        &[],
+    );
+    assert!(read_generations(&data).is_err());
+}
+
+#[test]
+fn truncated_netpbm_is_an_error() {
+    for data in [
+        &b"P4"[..],
+        b"P4 3",
+        b"P4 3 2",
+        b"P5 3 2 255",
+        b"P4 3 2\n\x80",
+        b"P5 3 2 255\n\0\0",
+    ] {
+        assert!(
+            read_generations(data).is_err(),
+            "{:?}",
+            String::from_utf8_lossy(data)
+        );
+    }
+}
+
+#[test]
+fn zero_width_netpbm_is_an_error() {
+    assert!(read_generations(b"P4 0 3\n").is_err());
+    assert!(read_generations(b"P5 0 3 255\n").is_err());
+}
+
+#[test]
+fn oversized_netpbm_is_an_error() {
+    let header = format!("P5 {} {} 255\n", usize::MAX, 2);
+    assert!(read_generations(header.as_bytes()).is_err());
+}
+
+/// Rule 30 from a random row, with blank rows and an odd width to exercise RLE's
+/// collapsed line ends and PBM's bit padding.
+fn sample_rows() -> Vec<Vec<u8>> {
+    let mut rows: Vec<Vec<u8>> = Automaton::new(30, Some(0.4), 13, Some(7))
+        .run(6)
+        .chunks(13)
+        .map(|row| row.to_vec())
+        .collect();
+    rows.insert(2, vec![0; 13]);
+    rows.insert(3, vec![0; 13]);
+    rows.push(vec![1; 13]);
+    rows.extend([vec![0; 13], vec![0; 13]]);
+    rows
+}
+
+fn write(format: DataFormat, rows: &[Vec<u8>]) -> Vec<u8> {
+    let mut writer = DataWriter::new(Vec::new(), format, 30, rows[0].len(), rows.len())
+        .expect("Writing to a Vec cannot fail");
+    for row in rows {
+        writer.write_row(row).expect("Writing to a Vec cannot fail");
+    }
+    writer.finish().expect("Writing to a Vec cannot fail")
+}
+
+#[test]
+fn every_format_round_trips() {
+    let rows = sample_rows();
+    for format in ["digits", "json", "csv", "pbm", "pgm", "rle", "npy"] {
+        let data = write(DataFormat::parse(format), &rows);
+        assert_eq!(read_generations(&data), Ok(rows.clone()), "{}", format);
+    }
+}
+
+#[test]
+fn rle_pads_rows_ending_in_dead_cells() {
+    let rows = vec![vec![0, 1, 0, 0], vec![0; 4], vec![1, 0, 0, 1]];
+    let data = write(DataFormat::Rle, &rows);
+    assert_eq!(read_generations(&data), Ok(rows));
+}
+
+#[test]
+fn rle_keeps_trailing_dead_generations() {
+    let mut rows = vec![vec![0; 8]; 4];
+    rows[0][4] = 1;
+    let data = write(DataFormat::Rle, &rows);
+    assert_eq!(data, b"x = 8, y = 4, rule = W30\n4bo!\n");
+    assert_eq!(read_generations(&data), Ok(rows));
+}
+
+#[test]
+fn rle_rejects_more_rows_than_header() {
+    assert!(read_generations(b"x = 3, y = 1, rule = W30\nbo$o!\n").is_err());
+}
@@ -0,0 +1,125 @@
+//! Permalinks must decode to exactly the config they were encoded from, and reject
+//! codes that are truncated, padded or from an unknown version.
+
+use ca::image_output::encode_png;
+use ca::permalink::{Config, PNG_KEYWORD};
+use ca::{Boundary, RuleFamily, SeedScheme};
+use image::Rgb;
+
+fn config() -> Config {
+    Config {
+        rule: 110,
+        random_distribution: None,
+        width: 64,
+        generations: 32,
+        seed: None,
+        seed_scheme: SeedScheme::LATEST,
+        family: RuleFamily::Elementary,
+        boundary: Boundary::Dead,
+        initial_row: None,
+        scale: 1,
+        alive_shape: "square".to_string(),
+        dead_shape: "square".to_string(),
+        links: false,
+        dead_from: Rgb([0xff, 0xaa, 0xff]),
+        dead_to: Rgb([0, 0, 0]),
+        alive_from: Rgb([0, 0, 0]),
+        alive_to: Rgb([0xaa, 0xff, 0xff]),
+    }
+}
+
+/// Every optional field set, with an odd width so the packed row has spare bits.
+fn full_config() -> Config {
+    Config {
+        rule: 30,
+        random_distribution: Some(0.35),
+        width: 13,
+        generations: 300,
+        seed: Some(u64::MAX),
+        seed_scheme: SeedScheme::V1,
+        family: RuleFamily::SecondOrder,
+        boundary: Boundary::Periodic,
+        initial_row: Some(vec![1, 0, 1, 1, 0, 0, 0, 1, 1, 1, 0, 1, 1]),
+        scale: 200,
+        alive_shape: "triangle-r-d".to_string(),
+        dead_shape: "circle-small".to_string(),
+        links: true,
+        ..config()
+    }
+}
+
+#[test]
+fn round_trips() {
+    for config in [config(), full_config()] {
+        let code = config.encode().expect("Config is encodable");
+        assert_eq!(Config::decode(&code), Ok(config));
+    }
+}
+
+#[test]
+fn decodes_urls() {
+    let code = full_config().encode().expect("Config is encodable");
+    let url = format!("https://example.com/ca/#{}", code);
+    assert_eq!(Config::decode(&url), Ok(full_config()));
+}
+
+#[test]
+fn rejects_unknown_shapes_and_mismatched_rows() {
+    let shape = Config {
+        alive_shape: "hexagon".to_string(),
+        ..config()
+    };
+    assert!(shape.encode().is_err());
+    let row = Config {
+        initial_row: Some(vec![1; 3]),
+        ..config()
+    };
+    assert!(row.encode().is_err());
+}
+
+#[test]
+fn rejects_truncated_and_padded_codes() {
+    let code = full_config().encode().expect("Config is encodable");
+    for len in 0..code.len() {
+        assert!(Config::decode(&code[..len]).is_err(), "length {}", len);
+    }
+    assert!(Config::decode(&format!("{}AA", code)).is_err());
+}
+
+#[test]
+fn rejects_unknown_versions() {
+    let code = config().encode().expect("Config is encodable");
+    // The first character holds the top six bits of the version byte
+    let future = format!("B{}", &code[1..]);
+    assert!(Config::decode(&future).is_err());
+}
+
+#[test]
+fn rejects_images_too_large_to_render() {
+    for (width, generations, scale) in [
+        (1 << 32, 1, 1),
+        (1, 1 << 31, 2),
+        (1 << 20, 1 << 20, 1 << 20),
+        (usize::MAX, usize::MAX, usize::MAX),
+    ] {
+        let huge = Config {
+            width,
+            generations,
+            scale,
+            ..config()
+        };
+        let code = huge.encode().expect("Config is encodable");
+        assert!(Config::decode(&code).is_err(), "{}x{}", width, generations);
+    }
+}
+
+#[test]
+fn round_trips_through_png_text() {
+    let config = full_config();
+    let code = config.encode().expect("Config is encodable");
+    let pixels = vec![0x80; 3 * 2 * 4];
+    let png = encode_png(&pixels, 3, 2, Some((PNG_KEYWORD, &code)));
+    assert_eq!(Config::from_png(&png), Ok(config));
+    assert!(Config::from_png(&encode_png(&pixels, 3, 2, None)).is_err());
+    assert!(Config::from_png(&png[..png.len() / 2]).is_err());
+}
@@ -0,0 +1,129 @@
+//! Preimage counts and searches must agree with stepping every row of a small width
+//! forward, for both boundaries.
+
+use ca::preimage::{backtrack, count_preimages, find_preimages, is_garden_of_eden};
+use ca::{step_row, Boundary};
+
+const BOUNDARIES: [Boundary; 2] = [Boundary::Dead, Boundary::Periodic];
+
+/// The cells of `bits` as a row of `width` cells, most significant first.
+fn row(bits: usize, width: usize) -> Vec<u8> {
+    (0..width)
+        .map(|i| (bits >> (width - 1 - i)) as u8 & 1)
+        .collect()
+}
+
+fn step(rule: u8, boundary: Boundary, current: &[u8]) -> Vec<u8> {
+    let mut next = vec![0; current.len()];
+    step_row(rule, boundary, current, &mut next);
+    next
+}
+
+/// Every row of `target.len()` cells that steps to `target`, in the order
+/// `find_preimages` lists them.
+fn brute_force(rule: u8, target: &[u8], boundary: Boundary) -> Vec<Vec<u8>> {
+    let width = target.len();
+    let mut preimages: Vec<Vec<u8>> = (0..1 << width)
+        .map(|bits| row(bits, width))
+        .filter(|candidate| step(rule, boundary, candidate) == target)
+        .collect();
+    if boundary == Boundary::Periodic {
+        preimages.sort_by_key(|preimage| preimage[width - 1]);
+    }
+    preimages
+}
+
+#[test]
+fn matches_brute_force() {
+    let width = 7;
+    for rule in [0, 30, 90, 110, 184, 255] {
+        for boundary in BOUNDARIES {
+            for bits in 0..1 << width {
+                let target = row(bits, width);
+                let expected = brute_force(rule, &target, boundary);
+                let context = format!("rule {} {:?} {:?}", rule, boundary, target);
+                assert_eq!(
+                    count_preimages(rule, &target, boundary),
+                    expected.len() as u128,
+                    "{}",
+                    context
+                );
+                assert_eq!(
+                    is_garden_of_eden(rule, &target, boundary),
+                    expected.is_empty(),
+                    "{}",
+                    context
+                );
+                assert_eq!(
+                    find_preimages(rule, &target, boundary, usize::MAX),
+                    expected,
+                    "{}",
+                    context
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn rule_90_periodic_even_width_has_four_or_no_preimages() {
+    // x[i - 1] ^ x[i + 1] = t[i] splits an even ring into two independent odd and even
+    // cycles, each solvable in two ways or not at all
+    let width = 8;
+    let counts: Vec<u128> = (0..1 << width)
+        .map(|bits| count_preimages(90, &row(bits, width), Boundary::Periodic))
+        .collect();
+    assert!(counts.iter().all(|&c| c == 0 || c == 4));
+    assert_eq!(counts.iter().filter(|&&c| c == 4).count(), 1 << (width - 2));
+}
+
+#[test]
+fn rule_90_dead_even_width_is_invertible() {
+    let width = 8;
+    for bits in 0..1 << width {
+        assert_eq!(count_preimages(90, &row(bits, width), Boundary::Dead), 1);
+    }
+}
+
+#[test]
+fn found_preimages_step_to_the_target() {
+    let target = step(30, Boundary::Dead, &row(0b1011_0010_1110_0101, 16));
+    for boundary in BOUNDARIES {
+        let preimages = find_preimages(30, &target, boundary, 8);
+        assert!(!preimages.is_empty());
+        assert!(preimages.len() <= 8);
+        for preimage in preimages {
+            assert_eq!(step(30, boundary, &preimage), target, "{:?}", boundary);
+        }
+    }
+}
+
+#[test]
+fn backtracked_history_steps_forward_to_the_target() {
+    for boundary in BOUNDARIES {
+        // A target with a known history, so backtracking cannot run into a Garden of Eden
+        let mut target = row(0b0110_1001_1100_0101_1010, 20);
+        for _ in 0..5 {
+            target = step(30, boundary, &target);
+        }
+        let history =
+            backtrack(30, &target, boundary, 6, usize::MAX).expect("The target has a history");
+        assert_eq!(history.len(), 6);
+        assert_eq!(history.last(), Some(&target));
+        for pair in history.windows(2) {
+            assert_eq!(step(30, boundary, &pair[0]), pair[1], "{:?}", boundary);
+        }
+    }
+}
+
+#[test]
+fn backtrack_fails_from_a_garden_of_eden() {
+    // Rule 0 kills everything, so no row steps to a live one
+    let target = vec![0, 1, 0];
+    assert!(is_garden_of_eden(0, &target, Boundary::Dead));
+    assert_eq!(backtrack(0, &target, Boundary::Dead, 2, 4), None);
+    assert_eq!(
+        backtrack(0, &target, Boundary::Dead, 1, 4),
+        Some(vec![target])
+    );
+}
@@ -0,0 +1,95 @@
+//! Incremental rendering must draw the same pixels as rendering the whole image at once,
+//! whether it is drawn in bands, into a reused buffer or scrolled for playback.
+
+use ca::image_output::{generations_to_rgba_buffer, CellShape, RgbaRenderer};
+use ca::Automaton;
+use image::Rgb;
+
+const WIDTH: usize = 29;
+const SCALE: usize = 4;
+const DEAD: (Rgb<u8>, Rgb<u8>) = (Rgb([255, 170, 255]), Rgb([16, 32, 48]));
+const ALIVE: (Rgb<u8>, Rgb<u8>) = (Rgb([0, 0, 0]), Rgb([170, 255, 255]));
+
+fn generations(count: usize) -> Vec<Vec<u8>> {
+    let mut automaton = Automaton::new(110, Some(0.5), WIDTH, Some(8));
+    let mut rows = vec![automaton.row().to_vec()];
+    for _ in 1..count {
+        automaton.step();
+        rows.push(automaton.row().to_vec());
+    }
+    rows
+}
+
+fn renderer(buffer: Vec<u8>, height: usize, colors: [Rgb<u8>; 4]) -> RgbaRenderer {
+    RgbaRenderer::new(
+        buffer,
+        WIDTH,
+        height,
+        SCALE,
+        CellShape::Circle,
+        CellShape::TriangleUp,
+        true,
+        colors[0],
+        colors[1],
+        colors[2],
+        colors[3],
+    )
+}
+
+fn one_shot(rows: &[Vec<u8>], colors: [Rgb<u8>; 4]) -> Vec<u8> {
+    generations_to_rgba_buffer(
+        rows,
+        WIDTH,
+        rows.len(),
+        SCALE,
+        "circle",
+        "triangle-up",
+        true,
+        colors[0],
+        colors[1],
+        colors[2],
+        colors[3],
+    )
+}
+
+#[test]
+fn finished_bands_match_the_whole_image() {
+    let colors = [DEAD.0, DEAD.1, ALIVE.0, ALIVE.1];
+    let rows = generations(40);
+    let expected = one_shot(&rows, colors);
+    let row_bytes = WIDTH * SCALE * SCALE * 4;
+    // Reuse a dirty buffer from a larger image, as `ImageRenderer::configure` does
+    let mut renderer = renderer(vec![0x5a; row_bytes * 100], rows.len(), colors);
+    let mut finished = 0;
+    for band in rows.chunks(7) {
+        for row in band {
+            renderer.push_row(row);
+        }
+        // Pixels reported finished must already be final
+        let now_finished = renderer.finished_rows();
+        assert_eq!(
+            renderer.buffer()[finished * row_bytes..now_finished * row_bytes],
+            expected[finished * row_bytes..now_finished * row_bytes]
+        );
+        finished = now_finished;
+    }
+    assert_eq!(finished, rows.len());
+    assert_eq!(renderer.into_buffer(), expected);
+}
+
+#[test]
+fn scrolled_window_matches_the_last_generations() {
+    // The gradient repeats every window while scrolling, so use flat colours
+    let colors = [DEAD.0, DEAD.0, ALIVE.1, ALIVE.1];
+    let window = 12;
+    let rows = generations(40);
+    let mut renderer = renderer(Vec::new(), window, colors);
+    for (generation, row) in rows.iter().enumerate() {
+        renderer.scroll_row(row, generation);
+    }
+    let expected = one_shot(&rows[rows.len() - window..], colors);
+    // The top generation keeps the half of its links to the one scrolled away
+    let row_bytes = WIDTH * SCALE * SCALE * 4;
+    assert_eq!(renderer.buffer()[row_bytes..], expected[row_bytes..]);
+    assert_eq!(renderer.buffer().len(), expected.len());
+}
@@ -0,0 +1,78 @@
+//! Rule tables must format and parse back to the same rule, in both transition strings
+//! and Golly tables.
+
+use ca::rule_table::{parse_rule, RuleTable};
+
+#[test]
+fn every_rule_round_trips_through_transition_strings() {
+    for rule in 0..=255 {
+        let text = RuleTable::from_rule(rule).to_string();
+        let table = RuleTable::parse_transitions(&text).expect("Formatted tables parse");
+        assert_eq!(table.to_rule(), Some(rule), "{}", text);
+        assert_eq!(parse_rule(&text), Ok(rule));
+    }
+}
+
+#[test]
+fn formats_from_111_down() {
+    assert_eq!(
+        RuleTable::from_rule(110).to_string(),
+        "111:0 110:1 101:1 100:0 011:1 010:1 001:1 000:0"
+    );
+}
+
+#[test]
+fn partial_tables_keep_unspecified_outputs() {
+    let table = RuleTable::parse_transitions("001:1, 110:0").expect("Valid transitions");
+    assert_eq!(table.to_rule(), None);
+    assert_eq!(
+        table.to_string(),
+        "111:? 110:0 101:? 100:? 011:? 010:? 001:1 000:?"
+    );
+    assert!(parse_rule("001:1, 110:0").is_err());
+}
+
+#[test]
+fn rejects_malformed_transitions() {
+    for text in ["111", "11:0", "111:2", "1a1:0", "111:0 111:1"] {
+        assert!(RuleTable::parse_transitions(text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn parses_golly_tables() {
+    // Rule 90: the new state is the XOR of the two neighbours
+    let table = "\
+n_states:2
+neighborhood:oneDimensional
+symmetries:reflect
+var a={0,1}
+a,0,0,0
+a,1,0,1 # and 0,1 by reflection
+a,1,1,0
+";
+    assert_eq!(parse_rule(table), Ok(90));
+    let rule_file = format!("@RULE Rule90\n@TABLE\n{}@COLORS\n1 255 255 255\n", table);
+    assert_eq!(parse_rule(&rule_file), Ok(90));
+}
+
+#[test]
+fn golly_tables_keep_unmatched_states() {
+    let table =
+        RuleTable::parse_golly("neighborhood:oneDimensional\n0,1,1,1\n").expect("Valid table");
+    // Only 101 changes; every other neighbourhood keeps its centre cell
+    assert_eq!(table.to_rule(), Some(0b1110_1100));
+}
+
+#[test]
+fn rejects_unsupported_golly_tables() {
+    for table in [
+        "n_states:3\n",
+        "neighborhood:Moore\n",
+        "symmetries:rotate4\n",
+        "0,1,1\n",
+        "b,1,1,1\n",
+    ] {
+        assert!(RuleTable::parse_golly(table).is_err(), "{}", table);
+    }
+}
@@ -0,0 +1,184 @@
+//! Golden values for every seed scheme. These must never change: published seeds
+//! rely on them to reproduce the same images on every platform and release.
+
+use ca::{Automaton, SeedScheme, UpdateMode};
+
+fn digits(row: &[u8]) -> String {
+    row.iter().map(|c| char::from(b'0' + c)).collect()
+}
+
+fn initial_row(seed: u64, scheme: SeedScheme) -> String {
+    digits(Automaton::new_with_scheme(30, Some(0.5), 64, Some(seed), scheme).row())
+}
+
+/// Rule 110 with noise and alpha-asynchronous updates, so the RNG is drawn from every step.
+fn noisy_run(scheme: SeedScheme) -> Vec<String> {
+    let mut automaton = Automaton::new_with_scheme(110, Some(0.5), 32, Some(2024), scheme)
+        .with_noise(0.1)
+        .with_update_mode(UpdateMode::Alpha(0.75));
+    let mut rows = vec![digits(automaton.row())];
+    for _ in 1..4 {
+        automaton.step();
+        rows.push(digits(automaton.row()));
+    }
+    rows
+}
+
+#[test]
+fn scheme_1_initial_rows() {
+    let cases = [
+        (
+            0,
+            "1111110011111111011111111110011110010101011111111100011001100011",
+        ),
+        (
+            1,
+            "1111111110111010101110010111100001110010100101101011011010101100",
+        ),
+        (
+            42,
+            "1111100011110011100011010100000011010101110100001010010101100010",
+        ),
+        (
+            u64::MAX,
+            "0001110001100001010000000010001110001011111011011110111000001100",
+        ),
+    ];
+    for (seed, row) in cases {
+        assert_eq!(initial_row(seed, SeedScheme::V1), row, "seed {}", seed);
+    }
+}
+
+#[test]
+fn scheme_2_initial_rows() {
+    let cases = [
+        (
+            0,
+            "1101000001110110001100111100000101110110111111001000101010100010",
+        ),
+        (
+            1,
+            "0000101111110111010000010000110000001111011010100001110001010010",
+        ),
+        (
+            42,
+            "0001011101100110010101000101011110101001100001100100100101000111",
+        ),
+        (
+            u64::MAX,
+            "0001101000101101001101001111001000101100010111010000001101010011",
+        ),
+    ];
+    for (seed, row) in cases {
+        assert_eq!(initial_row(seed, SeedScheme::V2), row, "seed {}", seed);
+    }
+}
+
+/// Generated with `rand` 0.10's `Xoshiro128PlusPlus`, the `SmallRng` of the wasm32 web UI.
+#[test]
+fn scheme_3_initial_rows() {
+    let cases = [
+        (
+            0,
+            "1100110100111110101100010110000011010001010000010110011000111100",
+        ),
+        (
+            1,
+            "1111110010011101111110000101001110100001010101110110010000101111",
+        ),
+        (
+            42,
+            "1111100101101001000101000010000001100101100000110011101100011110",
+        ),
+        (
+            u64::MAX,
+            "0000011011111001111011110011101110010011111010110010000101011101",
+        ),
+    ];
+    for (seed, row) in cases {
+        assert_eq!(initial_row(seed, SeedScheme::V1Wasm), row, "seed {}", seed);
+    }
+}
+
+#[test]
+fn fixed_buffer_initial_rows() {
+    let mut row = [0u8; 64];
+    for scheme in [SeedScheme::V1, SeedScheme::V2, SeedScheme::V1Wasm] {
+        for seed in [0, 1, 42, u64::MAX] {
+            ca::initial_row(&mut row, Some(0.5), seed, scheme);
+            assert_eq!(digits(&row), initial_row(seed, scheme), "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn fixed_buffer_empty_row() {
+    let mut row = [];
+    for random_distribution in [None, Some(0.5)] {
+        ca::initial_row(&mut row, random_distribution, 1, SeedScheme::LATEST);
+    }
+}
+
+#[test]
+fn scheme_1_noisy_run() {
+    assert_eq!(
+        noisy_run(SeedScheme::V1),
+        [
+            "11111110011011010011001001111001",
+            "10000010011110110011011000101001",
+            "10000110111010011111111001111011",
+            "00001111111110001001011011001111",
+        ]
+    );
+}
+
+#[test]
+fn scheme_2_noisy_run() {
+    assert_eq!(
+        noisy_run(SeedScheme::V2),
+        [
+            "11100001111001111111001101111100",
+            "11100001001011001101001111001100",
+            "10100011001111001100001001010100",
+            "11000111011111001100001111011100",
+        ]
+    );
+}
+
+#[test]
+fn scheme_3_noisy_run() {
+    assert_eq!(
+        noisy_run(SeedScheme::V1Wasm),
+        [
+            "11111110011111101011110000111010",
+            "11000010111100001100010010101010",
+            "11000011101000011000010111011010",
+            "11100111111100111010011101011010",
+        ]
+    );
+}
+
+#[test]
+fn default_is_latest_scheme() {
+    assert_eq!(SeedScheme::LATEST, SeedScheme::V2);
+    assert_eq!(
+        digits(Automaton::new(30, Some(0.5), 64, Some(42)).row()),
+        initial_row(42, SeedScheme::LATEST)
+    );
+}
+
+#[test]
+fn checkpoint_keeps_scheme() {
+    for scheme in [SeedScheme::V1, SeedScheme::V2, SeedScheme::V1Wasm] {
+        let mut automaton =
+            Automaton::new_with_scheme(30, Some(0.5), 40, Some(9), scheme).with_noise(0.2);
+        automaton.step();
+        let mut resumed = Automaton::from_checkpoint(&automaton.to_checkpoint()).unwrap();
+        assert_eq!(resumed.seed_scheme(), scheme);
+        for _ in 0..10 {
+            automaton.step();
+            resumed.step();
+            assert_eq!(automaton.row(), resumed.row());
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Per-generation statistics on rows whose values are known by hand.
+
+use ca::stats::{block_entropy, correlation, density, spatial_correlation, StatsCollector};
+
+#[test]
+fn density_counts_alive_cells() {
+    assert_eq!(density(&[]), 0.0);
+    assert_eq!(density(&[1, 0, 1, 1]), 0.75);
+}
+
+#[test]
+fn block_entropy_of_known_rows() {
+    // A constant row has a single block; an alternating one two equally likely blocks
+    assert_eq!(block_entropy(&[1; 16], 3), 0.0);
+    assert_eq!(block_entropy(&[0, 1, 0, 1, 0, 1, 0], 2), 1.0);
+    // Every 2-bit block appears exactly once
+    assert_eq!(block_entropy(&[0, 0, 1, 1, 0], 2), 2.0);
+    assert_eq!(block_entropy(&[1, 0], 3), 0.0);
+}
+
+#[test]
+fn correlation_of_known_rows() {
+    let row = [0, 1, 1, 0, 1, 0];
+    let inverse: Vec<u8> = row.iter().map(|c| c ^ 1).collect();
+    assert_eq!(correlation(&row, &row), Some(1.0));
+    assert_eq!(correlation(&row, &inverse), Some(-1.0));
+    assert_eq!(correlation(&[1, 1, 1], &[0, 1, 0]), None);
+    assert_eq!(spatial_correlation(&[0, 1, 0, 1, 0, 1], 2), Some(1.0));
+    assert_eq!(spatial_correlation(&[0, 1], 2), None);
+}
+
+#[test]
+fn collector_tracks_the_run() {
+    let mut collector = StatsCollector::new(&[1], &[1]);
+    let first = collector.push_row(&[0, 0, 1, 0, 0]);
+    assert_eq!(first.generation, 0);
+    assert_eq!(first.temporal_correlation, None);
+    assert_eq!(first.centre_density, 1.0);
+    let second = collector.push_row(&[0, 1, 0, 1, 0]);
+    assert_eq!(second.generation, 1);
+    assert_eq!(second.density, 0.4);
+    assert_eq!(second.centre_density, 0.5);
+    assert!(second.temporal_correlation.is_some_and(|c| c < 0.0));
+    assert_eq!(
+        collector.csv_header(),
+        "generation,density,entropy_1,spatial_corr_1,temporal_corr,centre_density"
+    );
+    assert_eq!(
+        first.to_csv(),
+        "0,0.2,0.7219280948873623,-0.3333333333333333,,1"
+    );
+    assert!(second.to_json().contains("\"temporal_correlation\":-"));
+}
@@ -0,0 +1,130 @@
+//! SVG output and the rule icon: document structure, and cells coloured as in the PNG
+//! output.
+
+use ca::image_output::{
+    format_hex_color, generations_to_rgba_buffer, generations_to_svg_elements, rule_icon_cells,
+    rule_icon_to_svg_elements, svg_document, RULE_ICON_HEIGHT, RULE_ICON_WIDTH,
+};
+use ca::Automaton;
+use image::Rgb;
+
+const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Value of attribute `name` in an SVG element.
+fn attribute<'a>(element: &'a str, name: &str) -> &'a str {
+    let start = element
+        .find(&format!(" {}=\"", name))
+        .unwrap_or_else(|| panic!("No {} in {}", name, element))
+        + name.len()
+        + 3;
+    let len = element[start..].find('"').expect("Attributes are quoted");
+    &element[start..start + len]
+}
+
+#[test]
+fn document_snapshot() {
+    let elements = generations_to_svg_elements(
+        &[vec![0, 1], vec![1, 0]],
+        2,
+        2,
+        3,
+        "square",
+        "circle",
+        false,
+        WHITE,
+        WHITE,
+        BLACK,
+        BLACK,
+        0,
+        0,
+    );
+    assert_eq!(
+        svg_document(&elements, 6, 6, Some(("permalink", "abc"))),
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"6\" height=\"6\" viewBox=\"0 0 6 6\">\n\
+         <metadata id=\"permalink\">abc</metadata>\n\
+         <circle cx=\"1.5\" cy=\"1.5\" r=\"1.5\" fill=\"#ffffff\"/>\n\
+         <rect x=\"3\" y=\"0\" width=\"3\" height=\"3\" fill=\"#000000\" shape-rendering=\"crispEdges\"/>\n\
+         <rect x=\"0\" y=\"3\" width=\"3\" height=\"3\" fill=\"#000000\" shape-rendering=\"crispEdges\"/>\n\
+         <circle cx=\"4.5\" cy=\"4.5\" r=\"1.5\" fill=\"#ffffff\"/>\n\
+         </svg>\n"
+    );
+}
+
+#[test]
+fn square_cells_match_the_png_colours() {
+    let (width, height, scale) = (11, 7, 2);
+    let mut automaton = Automaton::new(30, Some(0.5), width, Some(1));
+    let mut rows = vec![automaton.row().to_vec()];
+    for _ in 1..height {
+        automaton.step();
+        rows.push(automaton.row().to_vec());
+    }
+    let colors = [
+        Rgb([255, 170, 255]),
+        Rgb([16, 32, 48]),
+        Rgb([0, 0, 0]),
+        Rgb([170, 255, 255]),
+    ];
+    let elements = generations_to_svg_elements(
+        &rows, width, height, scale, "square", "square", false, colors[0], colors[1], colors[2],
+        colors[3], 5, 4,
+    );
+    let pixels = generations_to_rgba_buffer(
+        &rows, width, height, scale, "square", "square", false, colors[0], colors[1], colors[2],
+        colors[3],
+    );
+    let rects: Vec<&str> = elements.lines().collect();
+    assert_eq!(rects.len(), width * height);
+    for (i, rect) in rects.iter().enumerate() {
+        let (x, y) = (i % width, i / width);
+        assert!(rect.starts_with("<rect "));
+        assert_eq!(attribute(rect, "x"), (5 + x * scale).to_string());
+        assert_eq!(attribute(rect, "y"), (4 + y * scale).to_string());
+        let offset = (y * scale * width * scale + x * scale) * 4;
+        let pixel = Rgb([pixels[offset], pixels[offset + 1], pixels[offset + 2]]);
+        assert_eq!(attribute(rect, "fill"), format_hex_color(pixel));
+    }
+}
+
+#[test]
+fn rule_icon_shows_each_neighbourhood_and_its_output() {
+    let cells = rule_icon_cells(30);
+    assert_eq!(cells.len(), RULE_ICON_HEIGHT);
+    for (panel, neighbourhood) in (0..8u8).rev().enumerate() {
+        let left = panel * 4;
+        let top: Vec<Option<u8>> = (0..3).map(|i| Some(neighbourhood >> (2 - i) & 1)).collect();
+        assert_eq!(cells[0][left..left + 3], top);
+        assert_eq!(
+            cells[1][left..left + 3],
+            [None, Some(30 >> neighbourhood & 1), None]
+        );
+        if left + 3 < RULE_ICON_WIDTH {
+            assert_eq!((cells[0][left + 3], cells[1][left + 3]), (None, None));
+        }
+    }
+    // Rule 30 is 00011110: outputs from neighbourhood 111 down to 000
+    let outputs: Vec<u8> = (0..8)
+        .map(|panel| cells[1][panel * 4 + 1].expect("Panels have an output"))
+        .collect();
+    assert_eq!(outputs, [0, 0, 0, 1, 1, 1, 1, 0]);
+}
+
+#[test]
+fn rule_icon_svg_has_one_element_per_cell() {
+    let elements =
+        rule_icon_to_svg_elements(30, 4, "square", "circle", WHITE, WHITE, BLACK, BLACK, 0, 0);
+    let lines: Vec<&str> = elements.lines().collect();
+    assert_eq!(lines.len(), 8 * 4);
+    // 12 live neighbourhood cells and 4 live outputs are black squares
+    let alive = lines.iter().filter(|l| l.starts_with("<rect ")).count();
+    assert_eq!(alive, 16);
+    assert!(lines
+        .iter()
+        .filter(|l| l.starts_with("<rect "))
+        .all(|l| attribute(l, "fill") == "#000000"));
+    assert!(lines
+        .iter()
+        .filter(|l| l.starts_with("<circle "))
+        .all(|l| attribute(l, "fill") == "#ffffff"));
+}
@@ -0,0 +1,67 @@
+//! Rule symmetries must match reflecting and complementing the diagrams themselves.
+
+use ca::symmetry::{
+    canonical, complement, equivalence_classes, equivalents, is_canonical, mirror,
+    mirror_complement,
+};
+use ca::{step_row, Boundary};
+
+fn step(rule: u8, row: &[u8]) -> Vec<u8> {
+    let mut next = vec![0; row.len()];
+    step_row(rule, Boundary::Periodic, row, &mut next);
+    next
+}
+
+#[test]
+fn rule_30_equivalents() {
+    assert_eq!(mirror(30), 86);
+    assert_eq!(complement(30), 135);
+    assert_eq!(mirror_complement(30), 149);
+    assert_eq!(equivalents(30), vec![30, 86, 135, 149]);
+    assert_eq!(canonical(149), 30);
+}
+
+#[test]
+fn symmetries_are_involutions() {
+    for rule in 0..=255u8 {
+        assert_eq!(mirror(mirror(rule)), rule);
+        assert_eq!(complement(complement(rule)), rule);
+        assert_eq!(mirror_complement(mirror_complement(rule)), rule);
+        assert_eq!(mirror(complement(rule)), complement(mirror(rule)));
+    }
+}
+
+#[test]
+fn there_are_88_classes_partitioning_the_rules() {
+    let classes = equivalence_classes();
+    assert_eq!(classes.len(), 88);
+    let mut rules: Vec<u8> = classes.iter().flatten().copied().collect();
+    rules.sort_unstable();
+    assert_eq!(rules, (0..=255).collect::<Vec<u8>>());
+    for class in &classes {
+        assert!(is_canonical(class[0]));
+        assert!(class.iter().all(|&rule| equivalents(rule) == *class));
+    }
+    // Rule 90 is its own mirror and complement
+    assert!(classes.contains(&vec![90, 165]));
+}
+
+#[test]
+fn symmetries_transform_the_diagram() {
+    let row: Vec<u8> = (0..17u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 7) as u8 & 1)
+        .collect();
+    let reversed: Vec<u8> = row.iter().rev().copied().collect();
+    let inverted: Vec<u8> = row.iter().map(|c| 1 - c).collect();
+    for rule in 0..=255u8 {
+        let next = step(rule, &row);
+        let mut mirrored = step(mirror(rule), &reversed);
+        mirrored.reverse();
+        assert_eq!(mirrored, next, "rule {}", rule);
+        let complemented: Vec<u8> = step(complement(rule), &inverted)
+            .iter()
+            .map(|c| 1 - c)
+            .collect();
+        assert_eq!(complemented, next, "rule {}", rule);
+    }
+}
@@ -0,0 +1,71 @@
+//! Terminal renderers, checked through the CLI on rule 90 from a single centre cell:
+//!
+//! ```text
+//! ....#....
+//! ...#.#...
+//! ..#...#..
+//! .#.#.#.#.
+//! ```
+
+use std::process::Command;
+
+fn ca(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_ca"))
+        .args(["90", "-w", "9", "-g", "4", "--pretty-print"])
+        .args(args)
+        .output()
+        .expect("Failed to run ca");
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).expect("Output is UTF-8")
+}
+
+#[test]
+fn block_draws_one_cell_per_character() {
+    assert_eq!(
+        ca(&["--terminal", "block"]),
+        "    █    \n   █ █   \n  █   █  \n █ █ █ █ \n"
+    );
+}
+
+#[test]
+fn half_draws_two_generations_per_line() {
+    assert_eq!(ca(&["--terminal", "half"]), "   ▄▀▄   \n ▄▀▄ ▄▀▄ \n");
+}
+
+#[test]
+fn braille_draws_2x4_cells_per_character() {
+    // Dots 1-3 and 7 are the left column top to bottom, 4-6 and 8 the right
+    assert_eq!(
+        ca(&["--terminal", "braille"]),
+        "\u{2880}\u{2894}\u{2891}\u{2884}\u{2800}\n"
+    );
+}
+
+#[test]
+fn colour_sets_both_halves_of_each_character() {
+    let output = ca(&["--terminal", "half", "--color"]);
+    assert_eq!(output.lines().count(), 2);
+    for line in output.lines() {
+        let line = line.strip_suffix("\x1b[0m").expect("Colours are reset");
+        assert_eq!(line.matches('▀').count(), 9);
+        for cell in line.split_terminator('▀') {
+            let codes: Vec<&str> = cell.split('\x1b').skip(1).collect();
+            assert_eq!(codes.len(), 2, "{:?}", cell);
+            assert!(codes[0].starts_with("[38;2;"));
+            assert!(codes[1].starts_with("[48;2;"));
+        }
+    }
+}
+
+#[test]
+fn fit_conflicts_with_a_fixed_width() {
+    let row = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("fit_row.txt");
+    std::fs::write(&row, "0101\n").expect("Failed to write the row");
+    let output = Command::new(env!("CARGO_BIN_EXE_ca"))
+        .args(["90", "--fit", "--init"])
+        .arg(&row)
+        .output()
+        .expect("Failed to run ca");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
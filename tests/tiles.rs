@@ -0,0 +1,131 @@
+//! Tiles must show the same diagram as the image renderer: cell for cell at zoom 1 and
+//! averaged over blocks of cells when zoomed out.
+
+use ca::image_output::{try_parse_hex_color, CellShape, RgbaRenderer};
+use ca::{Automaton, TileViewer};
+
+const WIDTH: usize = 37;
+const GENERATIONS: usize = 23;
+const COLORS: [&str; 4] = ["#ffaaff", "#102030", "#000000", "#aaffff"];
+
+fn viewer() -> TileViewer {
+    TileViewer::new(
+        30,
+        Some(0.5),
+        WIDTH,
+        GENERATIONS,
+        Some(42),
+        None,
+        "elementary",
+        COLORS[0],
+        COLORS[1],
+        COLORS[2],
+        COLORS[3],
+        None,
+    )
+    .expect("The arguments are valid")
+}
+
+/// The whole diagram drawn one pixel per cell by `RgbaRenderer`.
+fn rendered() -> Vec<u8> {
+    let [dead_from, dead_to, alive_from, alive_to] =
+        COLORS.map(|c| try_parse_hex_color(c).expect("The colours are valid"));
+    let mut renderer = RgbaRenderer::new(
+        Vec::new(),
+        WIDTH,
+        GENERATIONS,
+        1,
+        CellShape::Square,
+        CellShape::Square,
+        false,
+        dead_from,
+        dead_to,
+        alive_from,
+        alive_to,
+    );
+    let mut automaton = Automaton::new(30, Some(0.5), WIDTH, Some(42));
+    renderer.push_row(automaton.row());
+    for _ in 1..GENERATIONS {
+        automaton.step();
+        renderer.push_row(automaton.row());
+    }
+    renderer.into_buffer()
+}
+
+fn tile(x: i32, y: i32, width: usize, height: usize, zoom: i32) -> Vec<u8> {
+    viewer()
+        .tile(x, y, width, height, zoom)
+        .expect("The zoom is valid")
+}
+
+#[test]
+fn zoom_1_tiles_match_the_rendered_image() {
+    let image = rendered();
+    assert_eq!(tile(0, 0, WIDTH, GENERATIONS, 1), image);
+    // A tile overhanging the top left corner is transparent outside the diagram
+    let overhanging = tile(-3, -2, 10, 8, 1);
+    for (j, line) in overhanging.chunks(10 * 4).enumerate() {
+        for (i, pixel) in line.chunks(4).enumerate() {
+            let (x, y) = (i as i64 - 3, j as i64 - 2);
+            if x < 0 || y < 0 {
+                assert_eq!(pixel, [0, 0, 0, 0]);
+            } else {
+                let offset = (y as usize * WIDTH + x as usize) * 4;
+                assert_eq!(pixel, &image[offset..offset + 4]);
+            }
+        }
+    }
+}
+
+#[test]
+fn zoomed_in_tiles_repeat_each_cell() {
+    let image = rendered();
+    let zoomed = tile(0, 0, WIDTH * 3, GENERATIONS * 3, 3);
+    for (j, line) in zoomed.chunks(WIDTH * 3 * 4).enumerate() {
+        for (i, pixel) in line.chunks(4).enumerate() {
+            let offset = ((j / 3) * WIDTH + i / 3) * 4;
+            assert_eq!(pixel, &image[offset..offset + 4]);
+        }
+    }
+}
+
+#[test]
+fn zoomed_out_pixels_average_the_cells_they_cover() {
+    let image = rendered();
+    let n = 4;
+    let (tile_width, tile_height) = (WIDTH.div_ceil(n), GENERATIONS.div_ceil(n));
+    let zoomed = tile(0, 0, tile_width, tile_height, -(n as i32));
+    for (j, line) in zoomed.chunks(tile_width * 4).enumerate() {
+        for (i, pixel) in line.chunks(4).enumerate() {
+            let mut sum = [0f64; 3];
+            let mut count = 0;
+            for y in j * n..((j + 1) * n).min(GENERATIONS) {
+                for x in i * n..((i + 1) * n).min(WIDTH) {
+                    let offset = (y * WIDTH + x) * 4;
+                    for (c, total) in sum.iter_mut().enumerate() {
+                        *total += image[offset + c] as f64;
+                    }
+                    count += 1;
+                }
+            }
+            // The tile averages exact gradient colours and the image rounds each cell's,
+            // so they may differ by one
+            for c in 0..3 {
+                let expected = sum[c] / count as f64;
+                assert!(
+                    (pixel[c] as f64 - expected).abs() <= 1.0,
+                    "pixel ({}, {}) channel {}: {} vs {}",
+                    i,
+                    j,
+                    c,
+                    pixel[c],
+                    expected
+                );
+            }
+            // Blocks cut off by the right and bottom edges are partly transparent
+            let alpha = (255.0 * count as f64 / (n * n) as f64).round() as u8;
+            assert_eq!(pixel[3], alpha, "pixel ({}, {})", i, j);
+        }
+    }
+    assert!(zoomed[zoomed.len() - 1] < 255);
+}
@@ -1,16 +1,230 @@
-import init, { run_automaton, generate_automaton_image } from './pkg/ca.js';
+import init, {
+  run_automaton,
+  estimate_wolfram_class,
+  rule_from_table,
+  encode_permalink,
+  Permalink,
+  Automaton,
+  export_png,
+  export_svg,
+  export_animation,
+} from './pkg/ca.js';
+import { RenderClient } from './render.js';
+import { TileView } from './viewer.js';
+
+// Cache of estimated Wolfram classes, filled lazily as rules are tried
+const ruleClasses = new Map();
+function ruleClass(rule) {
+  if (!ruleClasses.has(rule)) {
+    ruleClasses.set(rule, estimate_wolfram_class(rule));
+  }
+  return ruleClasses.get(rule);
+}
 
 
 async function main() {
-  await init();
+  const wasm = await init();
   const form = document.getElementById('params');
   const canvas = document.getElementById('output');
+  const client = new RenderClient();
+
+  // Initial row editor: the strip above the canvas shows the first generation, and
+  // clicking or dragging on it toggles cells. Edited rows replace the random or center
+  // row until Reset is pressed.
+  const strip = document.getElementById('initial-row');
+  let initialRow = null; // Uint8Array once edited
+  let shownRow = new Uint8Array(0); // row currently drawn on the strip
+  let paintValue = 1;
+  let regeneratePending = false;
+
+  function drawStrip(row, scale) {
+    const height = scale * Math.max(1, Math.ceil(12 / scale));
+    strip.width = row.length * scale;
+    strip.height = height;
+    const stripCtx = strip.getContext('2d');
+    const alive = document.getElementById('alive_color_from').value;
+    const dead = document.getElementById('dead_color_from').value;
+    row.forEach((cell, x) => {
+      stripCtx.fillStyle = cell ? alive : dead;
+      stripCtx.fillRect(x * scale, 0, scale, height);
+    });
+    shownRow = row;
+  }
+
+  function cellAt(e) {
+    const rect = strip.getBoundingClientRect();
+    const x = Math.floor(((e.clientX - rect.left) / rect.width) * shownRow.length);
+    return Math.min(Math.max(x, 0), shownRow.length - 1);
+  }
+
+  // Re-render at most once per frame while dragging
+  function regenerate() {
+    if (regeneratePending) return;
+    regeneratePending = true;
+    requestAnimationFrame(() => {
+      regeneratePending = false;
+      form.requestSubmit();
+    });
+  }
+
+  function paint(e) {
+    if (shownRow.length === 0) return;
+    if (!initialRow) initialRow = Uint8Array.from(shownRow);
+    const x = cellAt(e);
+    if (initialRow[x] === paintValue) return;
+    initialRow[x] = paintValue;
+    drawStrip(initialRow, strip.width / initialRow.length);
+    regenerate();
+  }
+
+  strip.addEventListener('pointerdown', (e) => {
+    if (shownRow.length === 0) return;
+    strip.setPointerCapture(e.pointerId);
+    paintValue = 1 - (initialRow ?? shownRow)[cellAt(e)];
+    paint(e);
+  });
+  strip.addEventListener('pointermove', (e) => {
+    if (strip.hasPointerCapture(e.pointerId)) paint(e);
+  });
+
+  // Playback: animates the settings of the last image, drawing one new generation per
+  // step at the bottom of the canvas straight from WASM memory
   const ctx = canvas.getContext('2d');
+  const playBtn = document.getElementById('play-btn');
+  const speedEl = document.getElementById('speed');
+  const playbackInfo = document.getElementById('playback-info');
+  let lastParams = null; // settings of the image on the canvas
+  let playback = null; // WASM Automaton while playing or paused
+  let playing = false;
+  let lastFrame = 0;
+  let dueSteps = 0;
+
+  function drawPlayback() {
+    const pixels = new Uint8ClampedArray(wasm.memory.buffer, playback.ptr(), playback.byte_len());
+    ctx.putImageData(new ImageData(pixels, playback.pixel_width(), playback.pixel_height()), 0, 0);
+    playbackInfo.textContent = `Generation ${playback.generation()}`;
+  }
+
+  // Stop any render so playback or the viewer can take over the canvas
+  function takeCanvas() {
+    client.cancel();
+    const genBtn = document.getElementById('generate-btn');
+    genBtn.disabled = false;
+    genBtn.textContent = 'Generate';
+    genBtn.classList.remove('simulating');
+  }
+
+  function startPlayback() {
+    if (playback || !lastParams) return;
+    stopViewer();
+    takeCanvas();
+    const p = lastParams;
+    playback = new Automaton(
+      p.rule,
+      p.random_distribution,
+      p.width,
+      p.generations,
+      p.seed,
+      p.seed_scheme,
+      p.rule_family,
+      p.scale,
+      p.alive_shape,
+      p.dead_shape,
+      p.links,
+      p.dead_color_from,
+      p.dead_color_to,
+      p.alive_color_from,
+      p.alive_color_to,
+      p.initial_row
+    );
+    canvas.width = playback.pixel_width();
+    canvas.height = playback.pixel_height();
+    drawPlayback();
+  }
+
+  function stopPlayback() {
+    playing = false;
+    playBtn.textContent = 'Play';
+    playbackInfo.textContent = '';
+    if (playback) {
+      playback.free();
+      playback = null;
+    }
+  }
+
+  function frame(time) {
+    if (!playing) return;
+    dueSteps += ((time - lastFrame) / 1000) * Number(speedEl.value);
+    lastFrame = time;
+    // Skip ahead at most one screen after the tab was in the background
+    const steps = Math.min(Math.floor(dueSteps), lastParams.generations);
+    if (steps > 0) {
+      dueSteps -= Math.floor(dueSteps);
+      playback.steps(steps);
+      drawPlayback();
+    }
+    requestAnimationFrame(frame);
+  }
+
+  playBtn.onclick = () => {
+    if (playing) {
+      playing = false;
+      playBtn.textContent = 'Play';
+      return;
+    }
+    startPlayback();
+    if (!playback) return;
+    playing = true;
+    playBtn.textContent = 'Pause';
+    lastFrame = performance.now();
+    dueSteps = 0;
+    requestAnimationFrame(frame);
+  };
+
+  document.getElementById('step-btn').onclick = () => {
+    startPlayback();
+    if (!playback) return;
+    playing = false;
+    playBtn.textContent = 'Play';
+    playback.step();
+    drawPlayback();
+  };
+
+  // Pan and zoom viewer for the settings of the last image
+  const viewerInfo = document.getElementById('viewer-info');
+  let tileView = null;
+
+  function stopViewer() {
+    viewerInfo.textContent = '';
+    if (tileView) {
+      tileView.destroy();
+      tileView = null;
+    }
+  }
+
+  document.getElementById('explore-btn').onclick = () => {
+    if (!lastParams) return;
+    stopPlayback();
+    stopViewer();
+    takeCanvas();
+    tileView = new TileView(canvas, lastParams, (text) => { viewerInfo.textContent = text; });
+  };
+
+  document.getElementById('reset-row').onclick = () => {
+    initialRow = null;
+    form.requestSubmit();
+  };
 
   // Randomize button logic
   document.getElementById('randomize').onclick = () => {
-    // Randomize rule
-    document.getElementById('rule').value = Math.floor(Math.random() * 256);
+    initialRow = null;
+    // Randomize rule (optionally only class 3/4 rules)
+    const interestingOnly = document.getElementById('interesting-only').checked;
+    let rule;
+    do {
+      rule = Math.floor(Math.random() * 256);
+    } while (interestingOnly && ruleClass(rule) < 3);
+    document.getElementById('rule').value = rule;
     // Randomize random distribution
     document.getElementById('random_distribution').value = (Math.random()).toFixed(2);
     // Randomize colors
@@ -53,14 +267,24 @@ async function main() {
 
   form.onsubmit = async (e) => {
     e.preventDefault();
+    stopPlayback();
+    stopViewer();
     console.log('Generation Time');
     const genBtn = document.getElementById('generate-btn');
     genBtn.disabled = true;
     genBtn.textContent = 'Simulating';
     genBtn.classList.add('simulating');
-    await new Promise(requestAnimationFrame); // Force browser repaint
-    await new Promise(resolve => setTimeout(resolve, 1)); // Ensure "Generating..." is visible before heavy computation
-    const rule = parseInt(document.getElementById('rule').value, 10);
+    // Accept a rule number or a transition table such as "111:0 110:1 ..."
+    let rule;
+    try {
+      rule = rule_from_table(document.getElementById('rule').value);
+    } catch (err) {
+      alert(`Invalid rule: ${err}`);
+      genBtn.disabled = false;
+      genBtn.textContent = 'Generate';
+      genBtn.classList.remove('simulating');
+      return;
+    }
     const width = parseInt(document.getElementById('width').value, 10);
     const generations = parseInt(document.getElementById('generations').value, 10);
     const randomDistStr = document.getElementById('random_distribution').value;
@@ -76,6 +300,7 @@ async function main() {
     const dead_color_to = document.getElementById('dead_color_to').value;
     const alive_color_from = document.getElementById('alive_color_from').value;
     const alive_color_to = document.getElementById('alive_color_to').value;
+    const rule_family = document.getElementById('rule-family').value;
     const seedStr = document.getElementById('seed').value;
     let seed = undefined;
     if (seedStr !== '') {
@@ -85,14 +310,61 @@ async function main() {
         seed = undefined;
       }
     }
+    const seed_scheme = parseInt(document.getElementById('seed-scheme').value, 10);
+
+    const damage = document.getElementById('damage').checked;
+    const damageRateEl = document.getElementById('damage-rate');
+    // Without an explicit seed, pick one so the strip and the image (and both damage
+    // calls) see the same initial row
+    if (seed === undefined) {
+      seed = BigInt(Math.floor(Math.random() * Number.MAX_SAFE_INTEGER));
+    }
+
+    // An edited row only applies while the width is unchanged
+    if (initialRow && initialRow.length !== width) {
+      initialRow = null;
+    }
+    drawStrip(
+      initialRow ?? run_automaton(rule, random_distribution, width, 1, seed, seed_scheme),
+      scale
+    );
+
+    // Keep the URL hash in sync so the address bar is always a permalink
+    try {
+      const code = encode_permalink(
+        rule,
+        random_distribution,
+        width,
+        generations,
+        seed,
+        seed_scheme,
+        rule_family,
+        scale,
+        alive_shape,
+        dead_shape,
+        links,
+        dead_color_from,
+        dead_color_to,
+        alive_color_from,
+        alive_color_to,
+        initialRow ?? undefined
+      );
+      history.replaceState(null, '', `#${code}`);
+    } catch (err) {
+      console.warn(`No permalink for these settings: ${err}`);
+    }
 
-    // Call new WASM function to generate RGBA buffer
-    const buffer = generate_automaton_image(
+    // Render in a worker, drawing bands as they finish
+    const params = {
       rule,
       random_distribution,
       width,
       generations,
       seed,
+      seed_scheme,
+      rule_family,
+      // The web UI always uses dead edges, as its permalinks do
+      boundary: 'dead',
       scale,
       alive_shape,
       dead_shape,
@@ -100,40 +372,119 @@ async function main() {
       dead_color_from,
       dead_color_to,
       alive_color_from,
-      alive_color_to
-    );
-
-    // Always resize canvas to fit the full automaton
-    const canvasWidth = width * scale;
-    const canvasHeight = generations * scale;
-    canvas.width = canvasWidth;
-    canvas.height = canvasHeight;
-    ctx.clearRect(0, 0, canvas.width, canvas.height);
-
-    // Render RGBA buffer to canvas
-    const imageData = new ImageData(new Uint8ClampedArray(buffer), canvasWidth, canvasHeight);
-    ctx.putImageData(imageData, 0, 0);
+      alive_color_to,
+      damage,
+      damage_color: document.getElementById('damage_color').value,
+      initial_row: initialRow ? Uint8Array.from(initialRow) : undefined,
+    };
+    lastParams = params;
+    let result;
+    try {
+      result = await client.render(params, canvas, (rows, total) => {
+        genBtn.textContent = `Simulating ${Math.floor((100 * rows) / total)}%`;
+      });
+    } catch (err) {
+      alert(`Rendering failed: ${err.message}`);
+      result = {};
+    }
+    // A newer render took over; leave the button to it
+    if (result.cancelled) return;
+    damageRateEl.textContent = result.damageRate === undefined
+      ? ''
+      : `Damage growth: ${result.damageRate.toFixed(3)} cells/generation`;
 
     genBtn.disabled = false;
     genBtn.textContent = 'Generate';
     genBtn.classList.remove('simulating');
   };
 
-}
+  // Fill the form from a permalink code and render it
+  function loadPermalink(code) {
+    let link;
+    try {
+      link = Permalink.decode(code);
+    } catch (err) {
+      alert(`Invalid permalink: ${err}`);
+      return;
+    }
+    const set = (id, value) => { document.getElementById(id).value = value; };
+    set('rule', link.rule());
+    set('random_distribution', link.random_distribution() ?? '');
+    set('width', link.width());
+    set('generations', link.generations());
+    set('seed', link.seed()?.toString() ?? '');
+    set('seed-scheme', link.seed_scheme());
+    set('rule-family', link.rule_family());
+    set('scale', link.scale());
+    set('alive-shape', link.alive_shape());
+    set('dead-shape', link.dead_shape());
+    document.getElementById('links').checked = link.links();
+    set('dead_color_from', link.bg_from());
+    set('dead_color_to', link.bg_to());
+    set('alive_color_from', link.fg_from());
+    set('alive_color_to', link.fg_to());
+    initialRow = link.initial_row() ?? null;
+    link.free();
+    form.requestSubmit();
+  }
 
-// Ensure the DOM fully loads and attach event to the download button
-window.addEventListener('DOMContentLoaded', () => {
-  const downloadBtn = document.getElementById('download-image');
-  const canvas = document.getElementById('output');
-  if (downloadBtn && canvas) {
-    downloadBtn.addEventListener('click', () => {
-      const link = document.createElement('a');
-      const timestamp = Date.now();
-      link.download = `ecars-${timestamp}.png`;
+  // Downloads are encoded by the same Rust code as the CLI, so a file saved here is
+  // byte-identical to the CLI's for the same settings and embeds their permalink. With
+  // damage, playback or the viewer on screen, PNG saves the canvas as shown instead.
+  document.getElementById('download-image').onclick = () => {
+    const format = document.getElementById('download-format').value;
+    const p = lastParams;
+    const link = document.createElement('a');
+    link.download = `ecars-${Date.now()}.${format === 'apng' ? 'png' : format}`;
+    if (!p || (format === 'png' && (p.damage || playback || tileView))) {
       link.href = canvas.toDataURL();
       link.click();
-    });
+      return;
+    }
+    const args = [
+      p.rule,
+      p.random_distribution,
+      p.width,
+      p.generations,
+      p.seed,
+      p.seed_scheme,
+      p.rule_family,
+      p.scale,
+      p.alive_shape,
+      p.dead_shape,
+      p.links,
+      p.dead_color_from,
+      p.dead_color_to,
+      p.alive_color_from,
+      p.alive_color_to,
+      p.initial_row,
+    ];
+    let blob;
+    try {
+      if (format === 'svg') {
+        blob = new Blob([export_svg(...args)], { type: 'image/svg+xml' });
+      } else if (format === 'png') {
+        blob = new Blob([export_png(...args)], { type: 'image/png' });
+      } else {
+        const rows = parseInt(document.getElementById('frame-rows').value, 10);
+        const delay = parseInt(document.getElementById('frame-delay').value, 10);
+        const bytes = export_animation(format, ...args, rows, delay);
+        blob = new Blob([bytes], { type: format === 'gif' ? 'image/gif' : 'image/apng' });
+      }
+    } catch (err) {
+      alert(`Export failed: ${err}`);
+      return;
+    }
+    link.href = URL.createObjectURL(blob);
+    link.click();
+    setTimeout(() => URL.revokeObjectURL(link.href), 0);
+  };
+
+  // Links pasted into the address bar of an open page change only the hash
+  window.addEventListener('hashchange', () => loadPermalink(location.hash.slice(1)));
+  if (location.hash.length > 1) {
+    loadPermalink(location.hash.slice(1));
   }
-});
+}
 
 main();
@@ -0,0 +1,56 @@
+// Renders automaton images in a Web Worker, drawing each band onto a canvas as it arrives.
+
+export class RenderClient {
+  constructor() {
+    this.worker = new Worker(new URL('./worker.js', import.meta.url), { type: 'module' });
+    this.job = 0;
+    this.pending = null;
+    this.worker.onmessage = (e) => this.handle(e.data);
+  }
+
+  // Render `params` (the arguments of generate_automaton_image, plus `damage`,
+  // `damage_color` and an optional `initial_row` Uint8Array) onto `canvas`. `onProgress(rows, total)` is called after each band.
+  // Resolves with `{ damageRate }` when done, or `{ cancelled: true }` if a newer
+  // render started first.
+  render(params, canvas, onProgress = () => {}) {
+    if (this.pending) {
+      this.pending.resolve({ cancelled: true });
+    }
+    this.job += 1;
+    canvas.width = params.width * params.scale;
+    canvas.height = params.generations * params.scale;
+    canvas.getContext('2d').clearRect(0, 0, canvas.width, canvas.height);
+    return new Promise((resolve, reject) => {
+      this.pending = { job: this.job, params, canvas, onProgress, resolve, reject };
+      this.worker.postMessage({ job: this.job, params });
+    });
+  }
+
+  // Stop the current render, leaving what was drawn so far on the canvas.
+  cancel() {
+    if (this.pending) {
+      this.pending.resolve({ cancelled: true });
+      this.pending = null;
+    }
+    this.job += 1;
+    this.worker.postMessage({ job: this.job, cancel: true });
+  }
+
+  handle(msg) {
+    const pending = this.pending;
+    if (!pending || msg.job !== pending.job) return;
+    if (msg.type === 'band') {
+      const { canvas, params } = pending;
+      const height = (msg.end - msg.start) * params.scale;
+      const image = new ImageData(new Uint8ClampedArray(msg.pixels), canvas.width, height);
+      canvas.getContext('2d').putImageData(image, 0, msg.start * params.scale);
+      pending.onProgress(msg.end, msg.total);
+    } else if (msg.type === 'done') {
+      this.pending = null;
+      pending.resolve({ damageRate: msg.damageRate });
+    } else if (msg.type === 'error') {
+      this.pending = null;
+      pending.reject(new Error(msg.message));
+    }
+  }
+}
@@ -0,0 +1,141 @@
+// Pan and zoom view of a run, drawn from tiles rendered by the WASM TileViewer. Tiles
+// are rendered on demand, a few per frame so panning stays smooth, and cached per zoom.
+import { TileViewer } from './pkg/ca.js';
+
+const TILE = 256;
+// Positive: pixels per cell; negative: cells per pixel, averaged
+const ZOOMS = [-64, -32, -16, -8, -4, -2, 1, 2, 4, 8, 16, 32];
+const MAX_TILES = 256;
+const FRAME_BUDGET_MS = 8;
+
+function pixelsPerCell(zoom) {
+  return zoom > 0 ? zoom : 1 / -zoom;
+}
+
+export class TileView {
+  // `params` are the render parameters built by the form; `onChange` receives a short
+  // description of the view whenever it changes.
+  constructor(canvas, params, onChange) {
+    this.canvas = canvas;
+    this.ctx = canvas.getContext('2d');
+    this.onChange = onChange;
+    this.viewer = new TileViewer(
+      params.rule,
+      params.random_distribution,
+      params.width,
+      params.generations,
+      params.seed,
+      params.seed_scheme,
+      params.rule_family,
+      params.dead_color_from,
+      params.dead_color_to,
+      params.alive_color_from,
+      params.alive_color_to,
+      params.initial_row
+    );
+    this.tiles = new Map();
+    this.framePending = false;
+
+    const area = canvas.closest('.canvas-container');
+    canvas.width = Math.floor(area.clientWidth * 0.95);
+    canvas.height = Math.floor(area.clientHeight * 0.8);
+
+    // Start with the whole width in view, or as close as the zoom levels allow
+    const fits = ZOOMS.filter((z) => params.width * pixelsPerCell(z) <= canvas.width);
+    this.level = ZOOMS.indexOf(fits.length ? fits[fits.length - 1] : ZOOMS[0]);
+    this.x = (params.width * this.scale() - canvas.width) / 2;
+    this.y = 0;
+
+    canvas.classList.add('panning');
+    this.abort = new AbortController();
+    const signal = this.abort.signal;
+    canvas.addEventListener('pointerdown', (e) => {
+      canvas.setPointerCapture(e.pointerId);
+    }, { signal });
+    canvas.addEventListener('pointermove', (e) => {
+      if (!canvas.hasPointerCapture(e.pointerId)) return;
+      const ratio = canvas.width / canvas.getBoundingClientRect().width;
+      this.x -= e.movementX * ratio;
+      this.y -= e.movementY * ratio;
+      this.draw();
+    }, { signal });
+    canvas.addEventListener('wheel', (e) => {
+      e.preventDefault();
+      const rect = canvas.getBoundingClientRect();
+      const ratio = canvas.width / rect.width;
+      this.zoomAt(e.deltaY < 0 ? 1 : -1, (e.clientX - rect.left) * ratio, (e.clientY - rect.top) * ratio);
+    }, { passive: false, signal });
+
+    this.draw();
+  }
+
+  scale() {
+    return pixelsPerCell(ZOOMS[this.level]);
+  }
+
+  // Change the zoom level by `delta`, keeping the cell under canvas pixel (px, py) in place
+  zoomAt(delta, px, py) {
+    const level = Math.min(Math.max(this.level + delta, 0), ZOOMS.length - 1);
+    if (level === this.level) return;
+    const cellX = (this.x + px) / this.scale();
+    const cellY = (this.y + py) / this.scale();
+    this.level = level;
+    this.x = cellX * this.scale() - px;
+    this.y = cellY * this.scale() - py;
+    this.draw();
+  }
+
+  draw() {
+    if (this.framePending) return;
+    this.framePending = true;
+    requestAnimationFrame(() => {
+      this.framePending = false;
+      this.drawFrame();
+    });
+  }
+
+  drawFrame() {
+    if (!this.viewer) return;
+    const start = performance.now();
+    const zoom = ZOOMS[this.level];
+    const x = Math.round(this.x);
+    const y = Math.round(this.y);
+    // Tiles covering both the canvas and the diagram
+    const right = Math.min(x + this.canvas.width, Math.ceil(this.viewer.width() * this.scale()));
+    const bottom = Math.min(y + this.canvas.height, Math.ceil(this.viewer.generations() * this.scale()));
+    this.ctx.clearRect(0, 0, this.canvas.width, this.canvas.height);
+    let missing = false;
+    for (let ty = Math.max(Math.floor(y / TILE), 0); ty * TILE < bottom; ty++) {
+      for (let tx = Math.max(Math.floor(x / TILE), 0); tx * TILE < right; tx++) {
+        const key = `${zoom}:${tx}:${ty}`;
+        let tile = this.tiles.get(key);
+        if (tile) {
+          // Move to the back of the eviction order
+          this.tiles.delete(key);
+        } else if (performance.now() - start < FRAME_BUDGET_MS) {
+          const pixels = this.viewer.tile(tx * TILE, ty * TILE, TILE, TILE, zoom);
+          tile = new ImageData(new Uint8ClampedArray(pixels.buffer), TILE, TILE);
+        } else {
+          missing = true;
+          continue;
+        }
+        this.tiles.set(key, tile);
+        this.ctx.putImageData(tile, tx * TILE - x, ty * TILE - y);
+      }
+    }
+    while (this.tiles.size > MAX_TILES) {
+      this.tiles.delete(this.tiles.keys().next().value);
+    }
+    if (missing) this.draw();
+    const label = zoom > 0 ? `${zoom}` : `1/${-zoom}`;
+    this.onChange(`Zoom ${label} px/cell, ${this.viewer.simulated_rows()} of ${this.viewer.generations()} generations simulated`);
+  }
+
+  destroy() {
+    this.abort.abort();
+    this.canvas.classList.remove('panning');
+    this.viewer.free();
+    this.viewer = null;
+    this.tiles.clear();
+  }
+}
@@ -0,0 +1,131 @@
+// Web Worker that renders automaton images off the main thread.
+// Use it through RenderClient in render.js rather than posting messages directly.
+import init, {
+  ImageRenderer,
+  generate_damage_image,
+  damage_growth,
+} from './pkg/ca.js';
+
+// Roughly how many pixels to draw between progress messages
+const BAND_PIXELS = 1 << 18;
+
+const ready = init();
+// Id of the newest job; older jobs stop at their next band
+let currentJob = 0;
+// Reused across jobs so its pixel buffer is only reallocated when the image grows
+let renderer = null;
+
+// Copy generations start..end straight out of WASM memory into a transferable buffer
+function postBand(job, wasm, p, start, end) {
+  const rowBytes = renderer.pixel_width() * p.scale * 4;
+  const pixels = new Uint8Array(
+    wasm.memory.buffer,
+    renderer.ptr() + start * rowBytes,
+    (end - start) * rowBytes
+  ).slice();
+  self.postMessage(
+    { job, type: 'band', start, end, total: p.generations, pixels: pixels.buffer },
+    [pixels.buffer]
+  );
+}
+
+async function renderImage(job, wasm, p) {
+  const args = [
+    p.rule,
+    p.random_distribution,
+    p.width,
+    p.generations,
+    p.seed,
+    p.seed_scheme,
+    p.rule_family,
+    p.scale,
+    p.alive_shape,
+    p.dead_shape,
+    p.links,
+    p.dead_color_from,
+    p.dead_color_to,
+    p.alive_color_from,
+    p.alive_color_to,
+  ];
+  if (renderer) {
+    renderer.configure(...args);
+  } else {
+    renderer = new ImageRenderer(...args);
+  }
+  if (p.initial_row) {
+    renderer.set_initial_row(p.initial_row);
+  }
+  const rowsPerBand = Math.max(1, Math.floor(BAND_PIXELS / (renderer.pixel_width() * p.scale)));
+  let sent = 0;
+  while (!renderer.is_done()) {
+    renderer.render_rows(rowsPerBand);
+    const finished = renderer.finished_rows();
+    if (finished > sent) {
+      postBand(job, wasm, p, sent, finished);
+      sent = finished;
+    }
+    // Yield so a newer job can cancel this one
+    await new Promise(resolve => setTimeout(resolve, 0));
+    if (job !== currentJob) return;
+  }
+  self.postMessage({ job, type: 'done' });
+}
+
+function renderDamage(job, p) {
+  const flipIndex = Math.floor(p.width / 2);
+  const pixels = generate_damage_image(
+    p.rule,
+    p.random_distribution,
+    p.width,
+    p.generations,
+    p.seed,
+    p.seed_scheme,
+    p.rule_family,
+    p.boundary,
+    flipIndex,
+    p.scale,
+    p.alive_shape,
+    p.dead_shape,
+    p.links,
+    p.dead_color_from,
+    p.dead_color_to,
+    p.alive_color_from,
+    p.alive_color_to,
+    p.damage_color,
+    p.initial_row
+  );
+  const rate = damage_growth(
+    p.rule,
+    p.random_distribution,
+    p.width,
+    p.generations,
+    p.seed,
+    p.seed_scheme,
+    p.rule_family,
+    p.boundary,
+    flipIndex,
+    p.initial_row
+  );
+  self.postMessage(
+    { job, type: 'band', start: 0, end: p.generations, total: p.generations, pixels: pixels.buffer },
+    [pixels.buffer]
+  );
+  self.postMessage({ job, type: 'done', damageRate: rate });
+}
+
+self.onmessage = async (e) => {
+  const { job, params, cancel } = e.data;
+  currentJob = job;
+  if (cancel) return;
+  const wasm = await ready;
+  if (job !== currentJob) return;
+  try {
+    if (params.damage) {
+      renderDamage(job, params);
+    } else {
+      await renderImage(job, wasm, params);
+    }
+  } catch (err) {
+    self.postMessage({ job, type: 'error', message: String(err) });
+  }
+};